@@ -0,0 +1,86 @@
+//! The review baseline: a commit whose history is accepted without review.
+//!
+//! The baseline is recorded locally in `.crev/baseline`. Commits reachable from
+//! it are left out by `status`, `log`, `coverage` and `verify`, so that review
+//! can be adopted by projects with a long history.
+use std::io::prelude::*;
+
+use crate::local;
+use crate::prelude::*;
+
+/// The commit recorded as the baseline, if any.
+pub fn load(local: &local::Local) -> Result<Option<git2::Oid>> {
+    let contents = std::fs::read_to_string(&local.baseline_path).unwrap_or_default();
+    let contents = contents.trim();
+    if contents.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(git2::Oid::from_str(contents).with_context(|_| {
+        format_err!("Invalid baseline in {}", local.baseline_path.display())
+    })?))
+}
+
+/// Record a commit as the baseline, or clear the baseline.
+pub fn store(local: &local::Local, baseline: Option<git2::Oid>) -> Result<()> {
+    match baseline {
+        Some(commit_id) => {
+            let mut file = std::fs::File::create(&local.baseline_path)?;
+            writeln!(file, "{}", commit_id)?;
+        }
+        None => {
+            if local.baseline_path.exists() {
+                std::fs::remove_file(&local.baseline_path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a commit is accepted by the baseline, i.e. reachable from it.
+pub fn contains(
+    repository: &git2::Repository,
+    baseline: Option<git2::Oid>,
+    commit_id: git2::Oid,
+) -> bool {
+    match baseline {
+        Some(baseline) => {
+            baseline == commit_id
+                || repository
+                    .graph_descendant_of(baseline, commit_id)
+                    .unwrap_or(false)
+        }
+        None => false,
+    }
+}
+
+/// Revision arguments excluding the history of the baseline from a walk.
+pub fn exclusions(baseline: Option<git2::Oid>) -> Vec<String> {
+    baseline.into_iter().map(|id| format!("^{}", id)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRepo;
+
+    #[test]
+    fn contains_the_history_of_the_baseline() {
+        let repo = TestRepo::new();
+        let root = repo.commit(&[], &[("a.txt", "a\n")], "Add a");
+        let baseline = repo.commit(&[root], &[("b.txt", "b\n")], "Add b");
+        let later = repo.commit(&[baseline], &[("c.txt", "c\n")], "Add c");
+
+        let repository = &repo.repository;
+        assert!(contains(repository, Some(baseline), root));
+        assert!(contains(repository, Some(baseline), baseline));
+        assert!(!contains(repository, Some(baseline), later));
+        assert!(!contains(repository, None, root));
+    }
+
+    #[test]
+    fn exclusions_hide_the_history_of_the_baseline() {
+        let id = git2::Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        assert_eq!(exclusions(Some(id)), vec![format!("^{}", id)]);
+        assert!(exclusions(None).is_empty());
+    }
+}
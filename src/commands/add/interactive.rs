@@ -2,96 +2,214 @@ use crate::prelude::*;
 
 use std::io::prelude::*;
 use std::io::{self, BufRead};
-use structopt::StructOpt;
-
-use crev_lib as crev;
 
+use crate::diff;
+use crate::editor;
 use crate::index;
 use crate::local;
+use crate::shared::parse_level;
+use crate::term;
+
+const HELP: &str = "\
+t - trust the commit (positive rating)
+T - trust the commit strongly (strong rating)
+n - neither trust nor distrust the commit (neutral rating)
+d - distrust the commit (negative rating)
+D - mark the commit as dangerous
+s - skip the commit without staging it
+b - go back to the previous commit
+r - show the commit again
+f - show only the files changed by the commit (diffstat)
+c - edit the review comment of the commit
+l - set thoroughness and understanding levels
+q - quit; decisions made so far are kept in the index
+? - show this help";
+
+/// A command given while reviewing a commit.
+#[derive(Debug, Clone, PartialEq)]
+enum ReviewCommand {
+    Rate(crev_data::Rating),
+    Skip,
+    Back,
+    Redisplay,
+    Stat,
+    Comment,
+    Levels,
+    Quit,
+    Help,
+}
 
-pub fn run(local: &local::Local) -> Result<()> {
-    let mut index = index::Index::load(&local.index_path)?;
-
-    let mut revwalk = local.repository.revwalk()?;
-    revwalk.push_head()?;
-    for commit_id in revwalk {
-        let commit_id = commit_id?;
-        if index.contains_commit_id(commit_id.to_string()) {
-            continue;
-        }
-
-        let mut git_command = std::process::Command::new("git")
-            .arg("log")
-            .arg("--patch")
-            .arg("-1")
-            .arg(commit_id.to_string())
-            .spawn()?;
-        git_command.wait()?;
+impl ReviewCommand {
+    fn from_key(key: char) -> Option<Self> {
+        Some(match key {
+            't' => ReviewCommand::Rate(crev_data::Rating::Positive),
+            'T' => ReviewCommand::Rate(crev_data::Rating::Strong),
+            'n' => ReviewCommand::Rate(crev_data::Rating::Neutral),
+            'd' => ReviewCommand::Rate(crev_data::Rating::Negative),
+            'D' => ReviewCommand::Rate(crev_data::Rating::Dangerous),
+            's' => ReviewCommand::Skip,
+            'b' => ReviewCommand::Back,
+            'r' => ReviewCommand::Redisplay,
+            'f' => ReviewCommand::Stat,
+            'c' => ReviewCommand::Comment,
+            'l' => ReviewCommand::Levels,
+            // Ctrl+C and Ctrl+D, as these are not signalled in raw mode.
+            'q' | '\u{3}' | '\u{4}' => ReviewCommand::Quit,
+            '?' | 'h' => ReviewCommand::Help,
+            _ => return None,
+        })
+    }
+}
 
-        let commands = parse_live_commands()?;
-        if commands.skip {
-            continue;
+/// Review commits one by one, interactively.
+///
+/// The index is saved after every decision, so the session can be left at any
+/// time.
+pub fn run(local: &local::Local, args: &super::Add) -> Result<()> {
+    let mut index = index::Index::load(&local.index_path)?;
+    let term = term::Term::new();
+
+    let commits = super::commits_to_review(local, args, &index)?;
+
+    let mut thoroughness = args.thoroughness.clone();
+    let mut understanding = args.understanding.clone();
+    let mut comment: Option<String> = None;
+    let mut show_commit = true;
+    let mut position = 0;
+    while position < commits.len() {
+        let commit = &commits[position];
+        let commit_id = commit.id().to_string();
+        if show_commit {
+            println!("commit {}/{}", position + 1, commits.len());
+            diff::show(&local.repository, commit, &args.diff_options)?;
+            if let Some(entry) = index.get(&commit_id) {
+                println!("(staged with rating {})", entry.review.rating);
+            }
+            show_commit = false;
         }
 
-        let commit = local.repository.find_commit(commit_id)?;
-        let mut new_entry: std::collections::BTreeSet<index::IndexEntry> =
-            std::collections::BTreeSet::new();
-        new_entry.insert(index::IndexEntry {
-            commit_id: commit.id().to_string(),
-            commit_summary: commit.summary().unwrap_or("").to_string(),
-        });
-
-        if commands.trust {
-            index.insert(&mut new_entry, &crev::TrustOrDistrust::Trust);
-        } else if commands.distrust {
-            index.insert(&mut new_entry, &crev::TrustOrDistrust::Distrust);
+        match read_command(&term)? {
+            ReviewCommand::Rate(rating) => {
+                if let Err(e) = super::check_paths(local, commit, &args.paths) {
+                    eprintln!("{}", e);
+                    continue;
+                }
+                let mut entry = index::IndexEntry::new(
+                    &local.repository,
+                    commit,
+                    crev_data::Review {
+                        thoroughness: thoroughness.clone(),
+                        understanding: understanding.clone(),
+                        rating,
+                    },
+                )?;
+                entry.paths = args.paths.clone();
+                entry.comment = comment
+                    .take()
+                    .or_else(|| index.get(&commit_id).map(|e| e.comment.clone()))
+                    .unwrap_or_else(|| args.comment.clone());
+                index.insert(entry);
+                index.dump(&local.index_path)?;
+                position += 1;
+                show_commit = true;
+            }
+            ReviewCommand::Skip => {
+                comment = None;
+                position += 1;
+                show_commit = true;
+            }
+            ReviewCommand::Back => {
+                if position == 0 {
+                    eprintln!("Already at the first commit.");
+                } else {
+                    comment = None;
+                    position -= 1;
+                    show_commit = true;
+                }
+            }
+            ReviewCommand::Redisplay => show_commit = true,
+            ReviewCommand::Stat => {
+                print!(
+                    "{}",
+                    diff::stat(&local.repository, commit, &args.diff_options)?
+                );
+            }
+            ReviewCommand::Comment => {
+                let current = comment
+                    .clone()
+                    .or_else(|| index.get(&commit_id).map(|e| e.comment.clone()))
+                    .unwrap_or_else(|| args.comment.clone());
+                let edited = editor::edit_comment(local, commit, &current)?;
+                // A commit which is already staged is updated right away.
+                if let Some(entry) = index.get(&commit_id) {
+                    let mut entry = entry.clone();
+                    entry.comment = edited;
+                    index.insert(entry);
+                    index.dump(&local.index_path)?;
+                } else {
+                    comment = Some(edited);
+                }
+            }
+            ReviewCommand::Levels => {
+                thoroughness = read_level("Thoroughness", thoroughness)?;
+                understanding = read_level("Understanding", understanding)?;
+            }
+            ReviewCommand::Quit => break,
+            ReviewCommand::Help => println!("{}", HELP),
         }
-
-        index.dump(&local.index_path)?;
     }
     Ok(())
 }
 
-fn parse_live_commands() -> Result<ReviewCommands> {
-    let stdin = io::stdin();
-
-    let mut line: String = "".into();
-    while line == "" {
-        print!("Review (skip:-s; trust:-t; distrust:-d): ");
+/// Read a single command, as a keystroke on a terminal or a line otherwise.
+fn read_command(term: &term::Term) -> Result<ReviewCommand> {
+    loop {
+        print!("Review this commit [t,T,n,d,D,s,b,r,f,c,l,q,?]? ");
         io::stdout().flush()?;
 
-        stdin.lock().read_line(&mut line)?;
-        line = line.trim().into();
-        if line == "" {
-            continue;
-        }
-
-        let mut commands: Vec<&str> = vec!["git"];
-        commands.extend(line.split(" "));
-
-        match ReviewCommands::from_iter_safe(commands) {
-            Ok(commands) => {
-                return Ok(commands);
+        let key = if term.stdin_is_tty {
+            let key = read_key()?;
+            println!("{}", key);
+            key
+        } else {
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line)? == 0 {
+                return Ok(ReviewCommand::Quit);
             }
-            Err(_) => {
-                eprintln!("Invalid commands: {}", line);
-                line = "".into();
-                continue;
+            match line.trim().chars().next() {
+                Some(key) => key,
+                None => continue,
             }
         };
-    }
 
-    Ok(ReviewCommands::default())
+        match ReviewCommand::from_key(key) {
+            Some(command) => return Ok(command),
+            None => eprintln!("Unknown command: {} (press ? for help)", key),
+        }
+    }
 }
 
-#[derive(Debug, Default, StructOpt, Clone)]
-pub struct ReviewCommands {
-    #[structopt(short = "s")]
-    pub skip: bool,
+/// Read a single keystroke, without waiting for Enter.
+fn read_key() -> Result<char> {
+    let _raw_screen = crossterm::RawScreen::into_raw_mode()?;
+    Ok(crossterm::input().read_char()?)
+}
 
-    #[structopt(short = "t")]
-    pub trust: bool,
+/// Prompt for a review level, keeping `current` on empty input.
+fn read_level(name: &str, current: crev_data::Level) -> Result<crev_data::Level> {
+    loop {
+        print!("{} (none, low, medium, high) [{}]: ", name, current);
+        io::stdout().flush()?;
 
-    #[structopt(short = "d")]
-    pub distrust: bool,
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(current);
+        }
+        match parse_level(line) {
+            Ok(level) => return Ok(level),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
 }
@@ -1,148 +1,264 @@
+use crate::commands::fetch;
+use crate::diff;
+use crate::editor;
 use crate::local;
 use crate::prelude::*;
+use crate::reviews;
+use crate::revision;
+use crate::shared::{self, parse_level, parse_rating};
 use structopt::StructOpt;
 
-use crev_lib as crev;
 use git2;
 
-use serde_yaml;
-
 use crate::index;
 mod interactive;
+mod tui;
 
 #[derive(Debug, StructOpt, Clone)]
 #[structopt(name = "add")]
 pub struct Add {
-    /// Git revision range
-    #[structopt(name = "revision range", default_value = "HEAD")]
-    pub revision_range: String,
+    #[structopt(flatten)]
+    pub revision_range: revision::RevisionRange,
 
+    /// Trust the commits (same as `--rating positive`)
     #[structopt(long = "trust", short = "t")]
     pub trust: bool,
 
+    /// Distrust the commits (same as `--rating negative`)
     #[structopt(long = "distrust", short = "d")]
     pub distrust: bool,
 
+    /// Review rating: dangerous, negative, neutral, positive or strong
+    #[structopt(long = "rating", short = "r", parse(try_from_str = "parse_rating"))]
+    pub rating: Option<crev_data::Rating>,
+
+    /// How thoroughly the commits were reviewed: none, low, medium or high
+    #[structopt(
+        long = "thoroughness",
+        default_value = "low",
+        parse(try_from_str = "parse_level")
+    )]
+    pub thoroughness: crev_data::Level,
+
+    /// How well the commits were understood: none, low, medium or high
+    #[structopt(
+        long = "understanding",
+        default_value = "medium",
+        parse(try_from_str = "parse_level")
+    )]
+    pub understanding: crev_data::Level,
+
+    /// Review comment
+    #[structopt(long = "comment", short = "m", default_value = "")]
+    pub comment: String,
+
+    /// Compose the review comment of each commit in the editor
+    #[structopt(long = "edit", short = "e")]
+    pub edit: bool,
+
     #[structopt(long = "interactive", short = "i")]
     pub interactive: bool,
+
+    /// Review commits in a full-screen terminal interface
+    #[structopt(long = "tui")]
+    pub tui: bool,
+
+    /// Review all files at the given revision rather than commits; commits in
+    /// its history then need no review of their own
+    #[structopt(long = "snapshot")]
+    pub snapshot: Option<String>,
+
+    /// Limit the review to paths under the given path or matching the given glob
+    #[structopt(long = "path")]
+    pub paths: Vec<String>,
+
+    #[structopt(flatten)]
+    pub diff_options: diff::DiffOptions,
 }
 
 /// Run 'add' subcommand.
 pub fn run_command(args: &Add) -> Result<()> {
     let local = local::Local::auto_create_or_open()?;
+    if args.snapshot.is_some()
+        && (args.interactive
+            || args.tui
+            || !args.revision_range.revisions.is_empty()
+            || !args.paths.is_empty())
+    {
+        bail!("--snapshot can't be combined with revisions, --path, --interactive or --tui");
+    }
     if args.interactive {
-        return interactive::run(&local);
+        return interactive::run(&local, args);
+    }
+    if args.tui {
+        return tui::run(&local, args);
     }
 
-    let trust_status = if args.trust {
-        crev::TrustOrDistrust::Trust
+    let rating = if let Some(rating) = &args.rating {
+        rating.clone()
+    } else if args.trust {
+        crev_data::Rating::Positive
     } else if args.distrust {
-        crev::TrustOrDistrust::Distrust
+        crev_data::Rating::Negative
     } else {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
-            "One of --trust or --distrust must be specified.",
+            "One of --trust, --distrust or --rating must be specified.",
         ))?;
     };
+    let review = crev_data::Review {
+        thoroughness: args.thoroughness.clone(),
+        understanding: args.understanding.clone(),
+        rating,
+    };
 
-    add_revision_range_commits(&args.revision_range, &local, &trust_status)?;
+    if let Some(revision) = &args.snapshot {
+        return add_snapshot(revision, &local, &review, &args.comment, args.edit);
+    }
+    add_revision_range_commits(
+        &args.revision_range,
+        &local,
+        &review,
+        &args.comment,
+        args.edit,
+        &args.paths,
+    )?;
     Ok(())
 }
 
-macro_rules! try_unwrap_res {
-    ($e:expr) => {
-        match $e {
-            Ok(t) => t,
-            Err(e) => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    e.to_string(),
-                ))?;
-            }
-        }
+/// Add a review of all files at a revision to the index.
+fn add_snapshot(
+    revision: &str,
+    local: &local::Local,
+    review: &crev_data::Review,
+    comment: &str,
+    edit: bool,
+) -> Result<()> {
+    let commit = local
+        .repository
+        .revparse_single(revision)?
+        .peel_to_commit()?;
+    let mut index = index::Index::load(&local.index_path)?;
+    let mut entry = index::IndexEntry::new_snapshot(&commit, review.clone());
+    entry.comment = if edit {
+        editor::edit_comment(local, &commit, comment)?
+    } else {
+        comment.to_string()
     };
+    index.insert(entry);
+    index.dump(&local.index_path)?;
+    Ok(())
 }
 
-macro_rules! try_unwrap_opt {
-    ($e:expr) => {
-        match $e {
-            Some(t) => t,
-            None => return Err(std::io::Error::new(std::io::ErrorKind::Other, "None"))?,
+/// Commits to review in an interactive session.
+///
+/// Without a revision range, the commits since the upstream of the current
+/// branch are reviewed. Commits which are already staged, or already reviewed
+/// by the current Id, are skipped.
+fn commits_to_review<'a>(
+    local: &'a local::Local,
+    args: &Add,
+    index: &index::Index,
+) -> Result<Vec<git2::Commit<'a>>> {
+    let commits = if args.revision_range.revisions.is_empty() {
+        revision::commits(
+            &local.repository,
+            &["@{upstream}..HEAD".into()],
+            &args.revision_range.walk_options,
+        )
+        .with_context(|_| {
+            format_err!("Could not find an upstream branch, please specify a revision range")
+        })?
+    } else {
+        args.revision_range.commits(&local.repository)?
+    };
+    // Only the current Id's reviews matter, so the trust graph isn't traversed.
+    let own_reviews = match crev_lib::Local::auto_open().and_then(|l| l.get_current_userid()) {
+        Ok(own_id) => {
+            let params = fetch::TrustDistanceParams::default().into();
+            Some((own_id, reviews::ReviewDb::load(&local.repository, &params)?))
         }
+        // Without an Id there are no own reviews yet.
+        Err(_) => None,
     };
+    let reviewed = |commit_id: &str| {
+        own_reviews.as_ref().map_or(false, |(own_id, db)| {
+            db.reviews_of(commit_id)
+                .iter()
+                .any(|review| review.review.common.from.id == *own_id)
+        })
+    };
+    Ok(commits
+        .into_iter()
+        .filter(|commit| {
+            let commit_id = commit.id().to_string();
+            !index.contains_commit_id(&commit_id) && !reviewed(&commit_id)
+        })
+        .collect())
 }
 
 /// Add commit(s) to the index based on a revision specification.
 pub fn add_revision_range_commits(
-    revision_specification: &str,
+    revision_range: &revision::RevisionRange,
     local: &local::Local,
-    trust_status: &crev::TrustOrDistrust,
+    review: &crev_data::Review,
+    comment: &str,
+    edit: bool,
+    paths: &[String],
 ) -> Result<()> {
-    let revision_specification =
-        local
-            .repository
-            .revparse(revision_specification)
-            .or(Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!(
-                    "Could not parse given revision specification: {}",
-                    revision_specification
-                ),
-            )))?;
-    let commits = commits_from_revision_specification(&revision_specification, &local.repository)?;
-    add_commits(&commits, &trust_status, &local)?;
+    let commits = revision_range.commits(&local.repository)?;
+    add_commits(&commits, review, comment, edit, paths, &local)?;
 
     Ok(())
 }
 
-/// Given a revision specification, returns the corresponding commits.
-fn commits_from_revision_specification<'a>(
-    revision_specification: &'a git2::Revspec,
-    repository: &'a git2::Repository,
-) -> Result<Vec<git2::Commit<'a>>> {
-    let from_commit = try_unwrap_opt!(try_unwrap_opt!(revision_specification.from()).as_commit());
-
-    if revision_specification.mode() == git2::RevparseMode::SINGLE {
-        return Ok(vec![from_commit.clone()]);
-    } else if revision_specification.mode() == git2::RevparseMode::RANGE {
-        let to_commit = try_unwrap_opt!(try_unwrap_opt!(revision_specification.to()).as_commit());
-
-        let mut revision_walk = try_unwrap_res!(repository.revwalk());
-        try_unwrap_res!(revision_walk.push(to_commit.id()));
-
-        let mut commits: Vec<git2::Commit> = Vec::new();
-        for commit_id in revision_walk {
-            let commit_id = try_unwrap_res!(commit_id);
-            if commit_id == from_commit.id() {
-                break;
-            }
-            let commit = try_unwrap_res!(repository.find_commit(commit_id));
-            commits.push(commit.clone());
-        }
-        return Ok(commits);
-    };
-
-    Ok(vec![])
-}
-
 /// Add commits to index file.
+///
+/// If `edit` is set, the comment of each commit is composed in the editor,
+/// starting from `comment`. If `paths` are given, the review of each commit is
+/// limited to them.
 fn add_commits(
     commits: &Vec<git2::Commit>,
-    trust_status: &crev::TrustOrDistrust,
+    review: &crev_data::Review,
+    comment: &str,
+    edit: bool,
+    paths: &[String],
     local: &local::Local,
 ) -> Result<()> {
-    // TODO: use into_iter here?
-    let mut new_entries: std::collections::BTreeSet<index::IndexEntry> =
-        std::collections::BTreeSet::new();
+    let mut index = index::Index::load(&local.index_path)?;
     for commit in commits {
-        new_entries.insert(index::IndexEntry {
-            commit_id: commit.id().to_string(),
-            commit_summary: commit.summary().unwrap_or("").to_string(),
-        });
+        check_paths(local, commit, paths)?;
+        let mut entry = index::IndexEntry::new(&local.repository, commit, review.clone())?;
+        entry.paths = paths.to_vec();
+        entry.comment = if edit {
+            editor::edit_comment(local, commit, comment)?
+        } else {
+            comment.to_string()
+        };
+        index.insert(entry);
     }
-
-    let mut index = index::Index::load(&local.index_path)?;
-    index.insert(&mut new_entries, trust_status);
     index.dump(&local.index_path)?;
     Ok(())
 }
+
+/// Ensure every pathspec matches a path changed by the commit.
+fn check_paths(local: &local::Local, commit: &git2::Commit, paths: &[String]) -> Result<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let changed_paths = shared::changed_paths(&local.repository, commit)?;
+    for pathspec in paths {
+        let mut matched = false;
+        for path in &changed_paths {
+            matched |= shared::path_matches(pathspec, path)?;
+        }
+        if !matched {
+            bail!(
+                "{} does not match any path changed by commit {}",
+                pathspec,
+                commit.id()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use crate::prelude::*;
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crossterm::{style, AlternateScreen, ClearType, Color, Crossterm, InputEvent, KeyEvent};
+
+use crate::diff;
+use crate::editor;
+use crate::index;
+use crate::local;
+use crate::shared;
+
+const KEYS: &str = "↑↓ select  PgUp/PgDn scroll  t trust  n neutral  d distrust  \
+                    s skip  c comment  q quit";
+
+/// Full-screen review of a list of commits.
+///
+/// The screen is split into a commit list, the files changed by the selected
+/// commit, and a scrollable diff. Every decision is saved to the index at once.
+///
+/// Diffs, summaries and paths are printed as plain text: the authors of the
+/// commits under review control them, so they must not be interpreted as
+/// markup or terminal escapes.
+struct Browser<'a, 'r> {
+    local: &'a local::Local,
+    args: &'a super::Add,
+    index: index::Index,
+    commits: Vec<git2::Commit<'r>>,
+    selected: usize,
+    diff_lines: Vec<String>,
+    /// First diff line shown.
+    diff_scroll: usize,
+    /// Number of diff lines shown at once, as of the last render.
+    diff_height: usize,
+}
+
+/// Smallest terminal the browser can be drawn in.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 5;
+
+pub fn run(local: &local::Local, args: &super::Add) -> Result<()> {
+    let index = index::Index::load(&local.index_path)?;
+    let commits = super::commits_to_review(local, args, &index)?;
+    if commits.is_empty() {
+        println!("No commits to review.");
+        return Ok(());
+    }
+
+    let crossterm = Crossterm::new();
+    let (width, height) = crossterm.terminal().terminal_size();
+    if width < MIN_WIDTH || height < MIN_HEIGHT {
+        bail!(
+            "The terminal is too small for --tui ({}x{}, needs at least {}x{})",
+            width,
+            height,
+            MIN_WIDTH,
+            MIN_HEIGHT
+        );
+    }
+
+    let mut browser = Browser {
+        local,
+        args,
+        index,
+        commits,
+        selected: 0,
+        diff_lines: vec![],
+        diff_scroll: 0,
+        diff_height: 1,
+    };
+    browser.select(0)?;
+
+    let mut screen = Some(AlternateScreen::to_alternate(true)?);
+    crossterm.cursor().hide()?;
+    let mut events = crossterm.input().read_sync();
+    loop {
+        browser.render(&crossterm)?;
+        let event = match events.next() {
+            Some(event) => event,
+            None => continue,
+        };
+        let key = match event {
+            InputEvent::Keyboard(key) => key,
+            _ => continue,
+        };
+        match key {
+            KeyEvent::Char('q') | KeyEvent::Esc | KeyEvent::Ctrl('c') => break,
+            KeyEvent::Up | KeyEvent::Char('k') => {
+                if browser.selected > 0 {
+                    browser.select(browser.selected - 1)?;
+                }
+            }
+            KeyEvent::Down | KeyEvent::Char('j') | KeyEvent::Char('s') => {
+                browser.select(browser.selected + 1)?;
+            }
+            KeyEvent::PageUp => browser.scroll_pages(-1),
+            KeyEvent::PageDown | KeyEvent::Char(' ') => browser.scroll_pages(1),
+            KeyEvent::Char('t') => browser.rate(crev_data::Rating::Positive)?,
+            KeyEvent::Char('n') => browser.rate(crev_data::Rating::Neutral)?,
+            KeyEvent::Char('d') => browser.rate(crev_data::Rating::Negative)?,
+            KeyEvent::Char('c') => {
+                // The editor needs the normal screen.
+                screen = None;
+                crossterm.cursor().show()?;
+                let result = browser.edit_comment();
+                screen = Some(AlternateScreen::to_alternate(true)?);
+                crossterm.cursor().hide()?;
+                result?;
+            }
+            _ => {}
+        }
+    }
+
+    crossterm.cursor().show()?;
+    drop(screen);
+    Ok(())
+}
+
+impl<'a, 'r> Browser<'a, 'r> {
+    fn commit(&self) -> &git2::Commit<'r> {
+        &self.commits[self.selected]
+    }
+
+    /// Select a commit, loading its diff into the diff pane.
+    fn select(&mut self, selected: usize) -> Result<()> {
+        if selected >= self.commits.len() {
+            return Ok(());
+        }
+        self.selected = selected;
+        let text = diff::render(
+            &self.local.repository,
+            self.commit(),
+            &self.args.diff_options,
+            false,
+        )?;
+        self.diff_lines = text.lines().map(plain_text).collect();
+        self.diff_scroll = 0;
+        Ok(())
+    }
+
+    /// Scroll the diff by whole pages, backwards for negative counts.
+    fn scroll_pages(&mut self, pages: isize) {
+        let lines = pages.abs() as usize * self.diff_height;
+        let last_page = self.diff_lines.len().saturating_sub(self.diff_height);
+        self.diff_scroll = if pages < 0 {
+            self.diff_scroll.saturating_sub(lines)
+        } else {
+            (self.diff_scroll + lines).min(last_page)
+        };
+    }
+
+    /// Stage the selected commit with a rating and move to the next one.
+    fn rate(&mut self, rating: crev_data::Rating) -> Result<()> {
+        super::check_paths(self.local, self.commit(), &self.args.paths)?;
+        let commit_id = self.commit().id().to_string();
+        let mut entry = index::IndexEntry::new(
+            &self.local.repository,
+            self.commit(),
+            crev_data::Review {
+                thoroughness: self.args.thoroughness.clone(),
+                understanding: self.args.understanding.clone(),
+                rating,
+            },
+        )?;
+        entry.paths = self.args.paths.clone();
+        entry.comment = self
+            .index
+            .get(&commit_id)
+            .map(|e| e.comment.clone())
+            .unwrap_or_else(|| self.args.comment.clone());
+        self.index.insert(entry);
+        self.index.dump(&self.local.index_path)?;
+        self.select(self.selected + 1)
+    }
+
+    /// Edit the comment of the selected commit, if it is staged.
+    fn edit_comment(&mut self) -> Result<()> {
+        let commit_id = self.commit().id().to_string();
+        let mut entry = match self.index.get(&commit_id) {
+            Some(entry) => entry.clone(),
+            None => return Ok(()),
+        };
+        entry.comment = editor::edit_comment(self.local, self.commit(), &entry.comment)?;
+        self.index.insert(entry);
+        self.index.dump(&self.local.index_path)?;
+        Ok(())
+    }
+
+    fn marker(&self, commit: &git2::Commit) -> (&'static str, Color) {
+        match self.index.get(&commit.id().to_string()) {
+            None => ("[ ]", Color::Grey),
+            Some(entry) => match entry.review.rating {
+                crev_data::Rating::Positive | crev_data::Rating::Strong => ("[+]", Color::Green),
+                crev_data::Rating::Neutral => ("[~]", Color::Yellow),
+                _ => ("[-]", Color::Red),
+            },
+        }
+    }
+
+    fn render(&mut self, crossterm: &Crossterm) -> Result<()> {
+        let terminal = crossterm.terminal();
+        let cursor = crossterm.cursor();
+        let (width, height) = terminal.terminal_size();
+        terminal.clear(ClearType::All)?;
+        if width < MIN_WIDTH || height < MIN_HEIGHT {
+            cursor.goto(0, 0)?;
+            print!("Terminal too small");
+            io::stdout().flush()?;
+            return Ok(());
+        }
+
+        let list_width = width / 3;
+        let list_height = (height - 1) / 2;
+
+        // Commit list, scrolled to keep the selected commit visible.
+        let offset = (self.selected + 1).saturating_sub(list_height as usize);
+        for (row, commit) in self
+            .commits
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(list_height as usize)
+        {
+            let (marker, color) = self.marker(commit);
+            let short_id: String = commit.id().to_string().chars().take(8).collect();
+            let line = fit(
+                &format!(
+                    "{} {} {}",
+                    marker,
+                    short_id,
+                    plain_text(commit.summary().unwrap_or(""))
+                ),
+                list_width,
+            );
+            cursor.goto(0, (row - offset) as u16)?;
+            if row == self.selected {
+                print!("{}", style(line).with(Color::Black).on(color));
+            } else {
+                print!("{}", style(line).with(color));
+            }
+        }
+
+        // Files changed by the selected commit.
+        let paths = shared::changed_paths(&self.local.repository, self.commit())?;
+        for (row, line) in file_tree(&paths)
+            .iter()
+            .take((height - 1 - list_height) as usize)
+            .enumerate()
+        {
+            cursor.goto(0, list_height + row as u16)?;
+            print!("{}", fit(&plain_text(line), list_width));
+        }
+
+        // Diff of the selected commit.
+        let diff_width = width - list_width - 1;
+        self.diff_height = (height - 1) as usize;
+        for (row, line) in self
+            .diff_lines
+            .iter()
+            .skip(self.diff_scroll)
+            .take(self.diff_height)
+            .enumerate()
+        {
+            cursor.goto(list_width + 1, row as u16)?;
+            let line = fit(line, diff_width);
+            let color = if line.starts_with("+++") || line.starts_with("---") {
+                Color::White
+            } else if line.starts_with('+') {
+                Color::Green
+            } else if line.starts_with('-') {
+                Color::Red
+            } else if line.starts_with("@@") {
+                Color::Cyan
+            } else {
+                Color::Reset
+            };
+            print!("{}", style(line).with(color));
+        }
+
+        cursor.goto(0, height - 1)?;
+        print!(
+            "{}",
+            style(fit(KEYS, width)).with(Color::Black).on(Color::Grey)
+        );
+        io::stdout().flush()?;
+        Ok(())
+    }
+}
+
+/// Truncate or pad `text` to exactly `width` characters.
+fn fit(text: &str, width: u16) -> String {
+    let text: String = text.chars().take(width as usize).collect();
+    format!("{:<width$}", text, width = width as usize)
+}
+
+/// Text from the commit under review with tabs expanded and other control
+/// characters, which could move the cursor or change colors, replaced.
+fn plain_text(line: &str) -> String {
+    line.chars()
+        .map(|c| match c {
+            '\t' => "    ".to_string(),
+            c if c.is_control() => char::REPLACEMENT_CHARACTER.to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Render paths as an indented tree, printing each directory once.
+fn file_tree(paths: &[PathBuf]) -> Vec<String> {
+    let mut paths = paths.to_vec();
+    paths.sort();
+    paths.dedup();
+
+    let mut lines = vec![];
+    let mut previous: Vec<String> = vec![];
+    for path in paths {
+        let components: Vec<String> = path
+            .iter()
+            .map(|c| c.to_string_lossy().into_owned())
+            .collect();
+        let common = previous
+            .iter()
+            .zip(&components)
+            .take_while(|(a, b)| a == b)
+            .count();
+        for (depth, component) in components.iter().enumerate().skip(common) {
+            let suffix = if depth + 1 < components.len() {
+                "/"
+            } else {
+                ""
+            };
+            lines.push(format!("{}{}{}", "  ".repeat(depth), component, suffix));
+        }
+        previous = components;
+    }
+    lines
+}
@@ -0,0 +1,82 @@
+use structopt::StructOpt;
+
+use crev_lib as crev;
+
+use crate::baseline;
+use crate::local;
+use crate::prelude::*;
+use crate::proof;
+use crate::shared::*;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Baseline {
+    /// Revision whose history is accepted without review [default: show the current baseline]
+    pub revision: Option<String>,
+
+    /// Remove the baseline
+    #[structopt(long = "clear")]
+    pub clear: bool,
+
+    /// Also publish the baseline as a signed proof
+    #[structopt(long = "sign")]
+    pub sign: bool,
+
+    #[structopt(flatten)]
+    pub common_proof_create: CommonProofCreate,
+}
+
+/// Run 'baseline' subcommand.
+///
+/// Commits reachable from the baseline are left out by `status`, `log`,
+/// `coverage` and `verify`. A signed baseline is accepted by anyone trusting
+/// its author, like a review of each commit in its history.
+pub fn run_command(args: &Baseline) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    if args.clear {
+        if args.revision.is_some() {
+            bail!("--clear can't be combined with a revision");
+        }
+        baseline::store(&local, None)?;
+        println!("Baseline cleared.");
+        return Ok(());
+    }
+
+    let revision = match &args.revision {
+        Some(revision) => revision,
+        None => {
+            match baseline::load(&local)? {
+                Some(commit_id) => {
+                    let commit = local.repository.find_commit(commit_id)?;
+                    println!("Baseline: {} {}", commit_id, commit.summary().unwrap_or(""));
+                }
+                None => println!("No baseline set."),
+            }
+            return Ok(());
+        }
+    };
+
+    let commit = local
+        .repository
+        .revparse_single(revision)?
+        .peel_to_commit()?;
+    baseline::store(&local, Some(commit.id()))?;
+    println!(
+        "Baseline set to {} {}",
+        commit.id(),
+        commit.summary().unwrap_or("")
+    );
+
+    if args.sign {
+        let crev_local = crev::Local::auto_open()?;
+        let own_id = crev_local.read_current_unlocked_id(&crev_common::read_passphrase)?;
+        let declaration = proof::CommitReview::new_baseline(
+            own_id.as_pubid().clone(),
+            &local.repository,
+            &commit,
+        )?;
+        let proof = declaration.sign_by(&own_id)?;
+        let commit_msg = format!("Add baseline at git commit {}", commit.id());
+        maybe_store(&crev_local, &proof, &commit_msg, &args.common_proof_create)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::collections::{hash_map::Entry, HashMap};
+use std::path::{Path, PathBuf};
+
+use structopt::StructOpt;
+
+use crate::commands::fetch;
+use crate::local;
+use crate::prelude::*;
+use crate::reviews;
+use crate::verification;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Blame {
+    /// File to annotate
+    #[structopt(parse(from_os_str))]
+    pub path: PathBuf,
+
+    /// Revision to annotate the file at
+    #[structopt(long = "rev", default_value = "HEAD")]
+    pub revision: String,
+
+    #[structopt(flatten)]
+    pub trust_params: fetch::TrustDistanceParams,
+}
+
+/// Run 'blame' subcommand.
+///
+/// Annotates each line of a file with the commit which introduced it and the
+/// review state of that commit, followed by a summary of the review coverage.
+pub fn run_command(args: &Blame) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let repository = &local.repository;
+    let verifier = verification::Verifier::load(repository, &args.trust_params.clone().into())?;
+
+    let path = repository_path(repository, &args.path)?;
+    let commit = repository
+        .revparse_single(&args.revision)?
+        .peel_to_commit()?;
+    let blob = commit
+        .tree()?
+        .get_path(&path)?
+        .to_object(repository)?
+        .peel_to_blob()?;
+    let content = String::from_utf8_lossy(blob.content());
+
+    let mut blame_options = git2::BlameOptions::new();
+    blame_options.newest_commit(commit.id());
+    let blame = repository.blame_file(&path, Some(&mut blame_options))?;
+
+    let mut statuses: HashMap<git2::Oid, reviews::Status> = HashMap::new();
+    let mut reviewed_lines = 0;
+    let mut total_lines = 0;
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        let hunk = match blame.get_line(line_number) {
+            Some(hunk) => hunk,
+            None => continue,
+        };
+        let commit_id = hunk.final_commit_id();
+        let status = match statuses.entry(commit_id) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                *entry.insert(verifier.status(&repository.find_commit(commit_id)?)?)
+            }
+        };
+
+        total_lines += 1;
+        if status == reviews::Status::Verified {
+            reviewed_lines += 1;
+        }
+        let short_id: String = commit_id.to_string().chars().take(8).collect();
+        println!("{} {:<10} {:>5}) {}", short_id, status, line_number, line);
+    }
+
+    let percentage = if total_lines == 0 {
+        100.0
+    } else {
+        100.0 * reviewed_lines as f64 / total_lines as f64
+    };
+    println!(
+        "\n{}: {} of {} line(s) reviewed ({:.1}%), {} unreviewed.",
+        path.display(),
+        reviewed_lines,
+        total_lines,
+        percentage,
+        total_lines - reviewed_lines
+    );
+    Ok(())
+}
+
+/// Path of a file relative to the repository root, given a path relative to
+/// the current directory.
+fn repository_path(repository: &git2::Repository, path: &Path) -> Result<PathBuf> {
+    if let Some(workdir) = repository.workdir() {
+        let absolute = std::env::current_dir()?.join(path);
+        let workdir = workdir.canonicalize()?;
+        let absolute = absolute.canonicalize().unwrap_or(absolute);
+        if let Ok(relative) = absolute.strip_prefix(&workdir) {
+            return Ok(relative.to_path_buf());
+        }
+    }
+    Ok(path.to_path_buf())
+}
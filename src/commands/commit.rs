@@ -0,0 +1,84 @@
+use structopt::StructOpt;
+
+use crev_lib as crev;
+
+use crate::index;
+use crate::local;
+use crate::notes;
+use crate::prelude::*;
+use crate::proof;
+use crate::shared::*;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Commit {
+    /// Store the proofs as Git notes on the reviewed commits instead of in the
+    /// proof repository (see `crev.notesRef`)
+    #[structopt(long = "notes")]
+    pub notes: bool,
+
+    #[structopt(flatten)]
+    pub common_proof_create: CommonProofCreate,
+}
+
+/// Run 'commit' subcommand.
+///
+/// Creates one signed commit review proof per staged index entry. Each entry is
+/// removed from the index once its proof has been stored, either in the proof
+/// repository or, with `--notes`, in the notes of the reviewed commit.
+pub fn run_command(args: &Commit) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let mut index = index::Index::load(&local.index_path)?;
+    if index.is_empty() {
+        println!("No commits staged.");
+        return Ok(());
+    }
+
+    let crev_local = crev::Local::auto_open()?;
+    let own_id = crev_local.read_current_unlocked_id(&crev_common::read_passphrase)?;
+
+    // Proofs are committed to the proof repository in one go once all are stored.
+    let mut proof_create_opt = args.common_proof_create.clone();
+    proof_create_opt.no_commit = true;
+    if args.notes {
+        proof_create_opt.no_store = true;
+    }
+
+    // Sign proofs for all staged commits before storing any, so that a staged
+    // commit missing from the repository doesn't leave some proofs stored but
+    // not committed.
+    let mut signed = vec![];
+    for entry in index.entries() {
+        let commit = git2::Oid::from_str(&entry.commit_id)
+            .and_then(|id| local.repository.find_commit(id))
+            .with_context(|_| {
+                format_err!(
+                    "Staged commit {} not found in the repository",
+                    entry.commit_id
+                )
+            })?;
+        let review =
+            proof::CommitReview::new(own_id.as_pubid().clone(), &local.repository, &commit, entry)?;
+        signed.push((entry.key(), commit.id(), review.sign_by(&own_id)?));
+    }
+
+    for (key, commit_id, proof) in &signed {
+        maybe_store(&crev_local, proof, "", &proof_create_opt)?;
+        if args.notes && !args.common_proof_create.no_store {
+            notes::store(&local.repository, *commit_id, proof)?;
+        }
+
+        if !args.common_proof_create.no_store {
+            index.remove(key);
+            index.dump(&local.index_path)?;
+        }
+    }
+
+    if !args.notes && !args.common_proof_create.no_store && !args.common_proof_create.no_commit {
+        let commit_msg = format!("Add reviews for {} git commit(s)", signed.len());
+        crev_local
+            .proof_dir_commit(&commit_msg)
+            .with_context(|_| format_err!("Could not not automatically commit"))?;
+    }
+
+    Ok(())
+}
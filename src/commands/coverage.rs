@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde;
+use structopt::StructOpt;
+
+use crate::baseline;
+use crate::commands::fetch;
+use crate::diff;
+use crate::local;
+use crate::prelude::*;
+use crate::reviews;
+use crate::revision;
+use crate::verification;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Coverage {
+    #[structopt(flatten)]
+    pub revision_range: revision::RevisionRange,
+
+    /// Only consider commits after the given revision (same as `<revision>..HEAD`)
+    #[structopt(long = "since")]
+    pub since: Option<String>,
+
+    /// Number of leading path components to group directories by
+    #[structopt(long = "directory-depth", default_value = "1")]
+    pub directory_depth: usize,
+
+    /// Print the report as JSON
+    #[structopt(long = "json")]
+    pub json: bool,
+
+    #[structopt(flatten)]
+    pub trust_params: fetch::TrustDistanceParams,
+}
+
+/// Reviewed share of a set of commits and of the lines they change.
+#[derive(Debug, Default, Clone, serde::Serialize)]
+struct Share {
+    commits: usize,
+    reviewed_commits: usize,
+    lines: usize,
+    reviewed_lines: usize,
+}
+
+impl Share {
+    fn add(&mut self, reviewed: bool, lines: usize) {
+        self.commits += 1;
+        self.lines += lines;
+        if reviewed {
+            self.reviewed_commits += 1;
+            self.reviewed_lines += lines;
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct Report {
+    total: Share,
+    by_directory: BTreeMap<String, Share>,
+    by_author: BTreeMap<String, Share>,
+    by_language: BTreeMap<String, Share>,
+}
+
+/// Run 'coverage' subcommand.
+///
+/// Without revisions, the whole history of `HEAD` since the baseline is
+/// considered.
+pub fn run_command(args: &Coverage) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let repository = &local.repository;
+    let verifier = verification::Verifier::load(repository, &args.trust_params.clone().into())?;
+
+    let mut revisions = args.revision_range.revisions.clone();
+    if let Some(since) = &args.since {
+        revisions.push(format!("^{}", since));
+    }
+    revisions.extend(baseline::exclusions(baseline::load(&local)?));
+    if revisions.iter().all(|r| r.starts_with('^')) {
+        revisions.push("HEAD".into());
+    }
+    let commits = revision::walk(repository, &revisions, &args.revision_range.walk_options)?;
+
+    let tokei_config = tokei::Config::default();
+    let mut report = Report::default();
+    for commit in &commits {
+        let reviewed = verifier.status(commit)? == reviews::Status::Verified;
+
+        // Changed lines per directory and language, counting each commit once per group.
+        let mut directories: BTreeMap<String, usize> = BTreeMap::new();
+        let mut languages: BTreeMap<String, usize> = BTreeMap::new();
+        let diff = diff::commit_diff(repository, commit, &diff::DiffOptions::exact())?;
+        for i in 0..diff.deltas().len() {
+            let patch = match git2::Patch::from_diff(&diff, i)? {
+                Some(patch) => patch,
+                None => continue,
+            };
+            let (_, additions, deletions) = patch.line_stats()?;
+            let delta = patch.delta();
+            let path = match delta.new_file().path().or_else(|| delta.old_file().path()) {
+                Some(path) => path,
+                None => continue,
+            };
+            let lines = additions + deletions;
+            *directories
+                .entry(directory(path, args.directory_depth))
+                .or_default() += lines;
+            let language = tokei::LanguageType::from_path(path, &tokei_config)
+                .map(|language| language.name().to_string())
+                .unwrap_or_else(|| "Other".into());
+            *languages.entry(language).or_default() += lines;
+        }
+
+        let lines: usize = directories.values().sum();
+        report.total.add(reviewed, lines);
+        let author = commit.author();
+        report
+            .by_author
+            .entry(format!(
+                "{} <{}>",
+                author.name().unwrap_or(""),
+                author.email().unwrap_or("")
+            ))
+            .or_default()
+            .add(reviewed, lines);
+        for (directory, lines) in directories {
+            report
+                .by_directory
+                .entry(directory)
+                .or_default()
+                .add(reviewed, lines);
+        }
+        for (language, lines) in languages {
+            report
+                .by_language
+                .entry(language)
+                .or_default()
+                .add(reviewed, lines);
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:<48} {:>18} {:>22}",
+        "", "Reviewed commits", "Reviewed lines"
+    );
+    print_row("Total", &report.total);
+    for (title, groups) in &[
+        ("By directory", &report.by_directory),
+        ("By author", &report.by_author),
+        ("By language", &report.by_language),
+    ] {
+        println!("\n{}:", title);
+        for (name, share) in groups.iter() {
+            print_row(&format!("  {}", name), share);
+        }
+    }
+    Ok(())
+}
+
+/// The first `depth` components of the directory of a path.
+fn directory(path: &Path, depth: usize) -> String {
+    let directory: Vec<String> = path
+        .parent()
+        .map(|parent| {
+            parent
+                .iter()
+                .take(depth)
+                .map(|c| c.to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    if directory.is_empty() {
+        ".".into()
+    } else {
+        directory.join("/")
+    }
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        100.0 * part as f64 / total as f64
+    }
+}
+
+fn print_row(name: &str, share: &Share) {
+    let name: String = name.chars().take(48).collect();
+    println!(
+        "{:<48} {:>10} {:>6.1}% {:>14} {:>6.1}%",
+        name,
+        format!("{}/{}", share.reviewed_commits, share.commits),
+        percentage(share.reviewed_commits, share.commits),
+        format!("{}/{}", share.reviewed_lines, share.lines),
+        percentage(share.reviewed_lines, share.lines)
+    );
+}
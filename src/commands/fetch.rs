@@ -3,7 +3,9 @@ use structopt::StructOpt;
 
 use crev_lib as crev;
 
+use crate::local;
 use crate::prelude::*;
+use crate::remote;
 
 pub fn run_command(subcommand: Fetch) -> Result<()> {
     match subcommand {
@@ -19,6 +21,23 @@ pub fn run_command(subcommand: Fetch) -> Result<()> {
             let local = crev::Local::auto_create_or_open()?;
             local.fetch_all()?;
         }
+        Fetch::Remote(params) => {
+            let local = local::Local::auto_create_or_open()?;
+            let names = match params.name {
+                Some(name) => vec![name],
+                None => local
+                    .repository
+                    .remotes()?
+                    .iter()
+                    .flatten()
+                    .map(String::from)
+                    .collect(),
+            };
+            for name in names {
+                let count = remote::fetch(&local.repository, &name)?;
+                println!("Fetched {} crev ref(s) from {}", count, name);
+            }
+        }
     }
     Ok(())
 }
@@ -36,6 +55,10 @@ pub enum Fetch {
     #[structopt(name = "all")]
     /// Fetch all previously retrieved public proof repositories
     All,
+
+    #[structopt(name = "remote")]
+    /// Fetch review notes and proofs published in the remotes of the current repository
+    Remote(FetchRemote),
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -44,6 +67,12 @@ pub struct FetchUrl {
     pub url: String,
 }
 
+#[derive(Debug, StructOpt, Clone)]
+pub struct FetchRemote {
+    /// Name of the remote [default: all remotes]
+    pub name: Option<String>,
+}
+
 /// Parameters describing trust graph traversal
 #[derive(Debug, StructOpt, Clone, Default)]
 pub struct TrustDistanceParams {
@@ -0,0 +1,558 @@
+use std::collections::{hash_map::Entry, HashMap};
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use crate::baseline;
+use crate::commands::fetch;
+use crate::local;
+use crate::prelude::*;
+use crate::reviews;
+use crate::revision;
+use crate::verification;
+
+/// Marks hook scripts installed by `git crev hooks install`.
+const MARKER: &str = "# git-crev hook";
+
+/// Setting this environment variable to a non-empty value skips all checks.
+const SKIP_VARIABLE: &str = "GIT_CREV_NO_VERIFY";
+
+pub fn run_command(subcommand: Hooks) -> Result<()> {
+    match subcommand {
+        Hooks::Install(args) => install(&args),
+        Hooks::Uninstall => uninstall(),
+        Hooks::Run(args) => run_hook(&args),
+    }
+}
+
+/// Manage Git hooks enforcing reviews.
+///
+/// The installed hooks refuse to push (or merge) commits which are not
+/// verified under the repository's review policy. They can be bypassed with
+/// `git push --no-verify` and `git merge --no-verify`, or by setting the
+/// `GIT_CREV_NO_VERIFY` environment variable to a non-empty value.
+///
+/// In a bare repository, a `pre-receive` hook is installed instead, which
+/// rejects pushes of commits that are not verified. The `update` hook is
+/// supported by `git crev hooks run` as well, for servers which want to
+/// reject individual refs rather than whole pushes.
+#[derive(Debug, StructOpt, Clone)]
+pub enum Hooks {
+    /// Install the hooks into the repository's hooks directory
+    #[structopt(name = "install")]
+    Install(Install),
+
+    /// Remove the hooks installed by `git crev hooks install`
+    #[structopt(name = "uninstall")]
+    Uninstall,
+
+    /// Run a hook; called by the installed hook scripts
+    #[structopt(name = "run")]
+    Run(Run),
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Install {
+    /// Also verify the commits merged by `git merge`
+    #[structopt(long = "pre-merge-commit")]
+    pub pre_merge_commit: bool,
+
+    /// Replace existing hooks not installed by git-crev
+    #[structopt(long = "force", short = "f")]
+    pub force: bool,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Run {
+    /// Name of the hook (`pre-push`, `pre-merge-commit`, `pre-receive` or `update`)
+    pub hook: String,
+
+    /// Arguments passed to the hook by Git
+    pub args: Vec<String>,
+
+    #[structopt(flatten)]
+    pub trust_params: fetch::TrustDistanceParams,
+}
+
+/// Hooks which can be installed, with the command bypassing them.
+const HOOKS: &[(&str, Option<&str>)] = &[
+    ("pre-push", Some("git push --no-verify")),
+    ("pre-merge-commit", Some("git merge --no-verify")),
+    ("pre-receive", None),
+];
+
+/// Directory Git runs hooks from, honoring `core.hooksPath`.
+fn hooks_dir(repository: &git2::Repository) -> Result<PathBuf> {
+    match repository.config()?.get_path("core.hooksPath") {
+        Ok(path) if path.is_absolute() => Ok(path),
+        // Like Git, resolve relative paths against the root of the working tree.
+        Ok(path) => Ok(repository
+            .workdir()
+            .unwrap_or_else(|| repository.path())
+            .join(path)),
+        Err(_) => Ok(repository.path().join("hooks")),
+    }
+}
+
+fn hook_script(hook: &str, bypass: Option<&str>) -> String {
+    let bypass = match bypass {
+        Some(bypass) => format!("use `{}` or set {}=1", bypass, SKIP_VARIABLE),
+        None => format!("set {}=1 in the environment of the server", SKIP_VARIABLE),
+    };
+    format!(
+        "#!/bin/sh\n\
+         {marker}: refuse commits without sufficient reviews.\n\
+         #\n\
+         # Installed by `git crev hooks install`, remove with `git crev hooks uninstall`.\n\
+         # To bypass this check, {bypass}.\n\
+         exec git crev hooks run {hook} \"$@\"\n",
+        marker = MARKER,
+        bypass = bypass,
+        hook = hook
+    )
+}
+
+fn is_installed_by_us(path: &std::path::Path) -> bool {
+    std::fs::read_to_string(path)
+        .map(|script| script.contains(MARKER))
+        .unwrap_or(false)
+}
+
+fn install(args: &Install) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let dir = hooks_dir(&local.repository)?;
+    std::fs::create_dir_all(&dir)?;
+
+    let bare = local.repository.is_bare();
+    for (hook, bypass) in HOOKS {
+        let wanted = match *hook {
+            "pre-push" => !bare,
+            "pre-merge-commit" => !bare && args.pre_merge_commit,
+            _ => bare,
+        };
+        if !wanted {
+            continue;
+        }
+        let path = dir.join(hook);
+        if path.exists() && !is_installed_by_us(&path) && !args.force {
+            bail!(
+                "{} already exists; use --force to replace it",
+                path.display()
+            );
+        }
+        std::fs::write(&path, hook_script(hook, *bypass))?;
+        make_executable(&path)?;
+        println!("Installed {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &std::path::Path) -> Result<()> {
+    Ok(())
+}
+
+fn uninstall() -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let dir = hooks_dir(&local.repository)?;
+    for (hook, _) in HOOKS {
+        let path = dir.join(hook);
+        if !path.exists() {
+            continue;
+        }
+        if is_installed_by_us(&path) {
+            std::fs::remove_file(&path)?;
+            println!("Removed {}", path.display());
+        } else {
+            eprintln!("Leaving {}: not installed by git-crev", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn run_hook(args: &Run) -> Result<()> {
+    if std::env::var(SKIP_VARIABLE).map_or(false, |value| !value.is_empty()) {
+        return Ok(());
+    }
+    let mut local = local::Local::auto_create_or_open()?;
+
+    let stdin = std::io::stdin();
+    let mut updates = match args.hook.as_str() {
+        "pre-push" => {
+            let remote = args.args.get(0).map(String::as_str).unwrap_or("");
+            pre_push_updates(&local.repository, remote, stdin.lock())?
+        }
+        "pre-merge-commit" => {
+            let mut revisions = vec!["^HEAD".to_string()];
+            local.repository.mergehead_foreach(|id| {
+                revisions.push(id.to_string());
+                true
+            })?;
+            vec![Update {
+                name: "MERGE_HEAD".into(),
+                revisions,
+            }]
+        }
+        "pre-receive" => pre_receive_updates(&local.repository, stdin.lock())?,
+        "update" => {
+            if args.args.len() != 3 {
+                bail!("The update hook takes <ref> <old> <new> as arguments");
+            }
+            received_update(
+                &local.repository,
+                &args.args[0],
+                &args.args[1],
+                &args.args[2],
+            )?
+            .into_iter()
+            .collect()
+        }
+        hook => bail!("Unsupported hook: {}", hook),
+    };
+    let exclusions = baseline::exclusions(baseline::load(&local)?);
+    for update in &mut updates {
+        update.revisions.extend(exclusions.iter().cloned());
+    }
+
+    let bypass = HOOKS
+        .iter()
+        .find(|(name, _)| *name == args.hook)
+        .and_then(|(_, bypass)| *bypass);
+    enforce(
+        &local.repository,
+        &updates,
+        &args.trust_params.clone().into(),
+        bypass,
+    )
+}
+
+/// A ref about to be updated, with the revisions selecting its new commits.
+struct Update {
+    name: String,
+    revisions: Vec<String>,
+}
+
+/// Updates about to be pushed, given the lines Git passes to the `pre-push`
+/// hook on its standard input: `<local ref> <local sha> <remote ref> <remote sha>`.
+fn pre_push_updates(
+    repository: &git2::Repository,
+    remote: &str,
+    input: impl BufRead,
+) -> Result<Vec<Update>> {
+    let mut updates = vec![];
+    for line in input.lines() {
+        let line = line?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 4 {
+            bail!("Unexpected pre-push hook input: {}", line);
+        }
+        let (local_sha, remote_ref, remote_sha) = (fields[1], fields[2], fields[3]);
+        if is_zero(local_sha) {
+            // Deleting a remote ref pushes no commits.
+            continue;
+        }
+
+        let mut revisions = vec![local_sha.to_string()];
+        if !is_zero(remote_sha) && is_commit(repository, remote_sha) {
+            revisions.push(format!("^{}", remote_sha));
+        } else {
+            // A new ref: skip everything the remote is already known to have.
+            revisions.extend(excluded_refs(
+                repository,
+                &format!("refs/remotes/{}/*", remote),
+            )?);
+        }
+        updates.push(Update {
+            name: remote_ref.to_string(),
+            revisions,
+        });
+    }
+    Ok(updates)
+}
+
+/// Updates received by a server, given the lines Git passes to the
+/// `pre-receive` hook on its standard input: `<old> <new> <ref>`.
+fn pre_receive_updates(repository: &git2::Repository, input: impl BufRead) -> Result<Vec<Update>> {
+    let mut updates = vec![];
+    for line in input.lines() {
+        let line = line?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 3 {
+            bail!("Unexpected pre-receive hook input: {}", line);
+        }
+        updates.extend(received_update(
+            repository, fields[2], fields[0], fields[1],
+        )?);
+    }
+    Ok(updates)
+}
+
+/// A single ref update received by a server, unless it deletes the ref.
+fn received_update(
+    repository: &git2::Repository,
+    name: &str,
+    old: &str,
+    new: &str,
+) -> Result<Option<Update>> {
+    if is_zero(new) {
+        return Ok(None);
+    }
+    let mut revisions = vec![new.to_string()];
+    if is_zero(old) {
+        // A new ref: only commits not reachable from any existing ref are new.
+        revisions.extend(excluded_refs(repository, "refs/*")?);
+    } else {
+        revisions.push(format!("^{}", old));
+    }
+    Ok(Some(Update {
+        name: name.to_string(),
+        revisions,
+    }))
+}
+
+fn is_zero(sha: &str) -> bool {
+    sha.chars().all(|c| c == '0')
+}
+
+fn is_commit(repository: &git2::Repository, sha: &str) -> bool {
+    git2::Oid::from_str(sha)
+        .and_then(|id| repository.find_commit(id))
+        .is_ok()
+}
+
+/// Exclusions for the commits of all refs matching a glob. Refs not pointing
+/// to commits, such as tags of trees, are skipped.
+fn excluded_refs(repository: &git2::Repository, glob: &str) -> Result<Vec<String>> {
+    let mut revisions = vec![];
+    for reference in repository.references_glob(glob)? {
+        if let Ok(commit) = reference?.peel_to_commit() {
+            revisions.push(format!("^{}", commit.id()));
+        }
+    }
+    Ok(revisions)
+}
+
+/// Verify the new commits of ref updates, report those which fail, and exit
+/// with a non-zero status if there are any.
+fn enforce(
+    repository: &git2::Repository,
+    updates: &[Update],
+    params: &crev_lib::TrustDistanceParams,
+    bypass: Option<&str>,
+) -> Result<()> {
+    let verifier = verification::Verifier::load(repository, params)?;
+
+    let mut statuses: HashMap<git2::Oid, reviews::Status> = HashMap::new();
+    let mut rejected = 0;
+    for update in updates {
+        if update.revisions.iter().all(|r| r.starts_with('^')) {
+            continue;
+        }
+        let commits = revision::walk(repository, &update.revisions, &Default::default())?;
+        let mut failures = vec![];
+        for commit in &commits {
+            let status = match statuses.entry(commit.id()) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => *entry.insert(verifier.status(commit)?),
+            };
+            if status != reviews::Status::Verified {
+                failures.push((commit, status));
+            }
+        }
+        if failures.is_empty() {
+            continue;
+        }
+
+        rejected += 1;
+        eprintln!(
+            "git-crev: {}: {} of {} new commit(s) failed verification:",
+            update.name,
+            failures.len(),
+            commits.len()
+        );
+        for (commit, status) in failures {
+            let short_id: String = commit.id().to_string().chars().take(8).collect();
+            eprintln!(
+                "    {:<10}  {}  {}",
+                status,
+                short_id,
+                commit.summary().unwrap_or("")
+            );
+        }
+    }
+
+    if rejected > 0 {
+        eprintln!(
+            "\ngit-crev: rejected {} ref(s). Review policy: every commit needs {}.",
+            rejected,
+            verifier.policy.base_requirement()
+        );
+        if let Some(bypass) = bypass {
+            eprintln!(
+                "Review the commits with `git crev add`, or bypass this check with `{}` or {}=1.",
+                bypass, SKIP_VARIABLE
+            );
+        }
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRepo;
+
+    const ZERO: &str = "0000000000000000000000000000000000000000";
+
+    /// A repository with a commit known to be on `origin`, and a new one.
+    struct Pushed {
+        repo: TestRepo,
+        base: git2::Oid,
+        tip: git2::Oid,
+    }
+
+    impl Pushed {
+        fn new() -> Self {
+            let repo = TestRepo::new();
+            let base = repo.commit(&[], &[("a.txt", "a\n")], "Add a");
+            let tip = repo.commit(&[base], &[("b.txt", "b\n")], "Add b");
+            repo.repository
+                .reference("refs/remotes/origin/master", base, false, "")
+                .unwrap();
+            Self { repo, base, tip }
+        }
+
+        fn pre_push(&self, input: &str) -> Result<Vec<Update>> {
+            pre_push_updates(&self.repo.repository, "origin", input.as_bytes())
+        }
+    }
+
+    #[test]
+    fn pre_push_of_updated_ref() {
+        let p = Pushed::new();
+        let updates = p
+            .pre_push(&format!(
+                "refs/heads/master {} refs/heads/master {}\n",
+                p.tip, p.base
+            ))
+            .unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].name, "refs/heads/master");
+        assert_eq!(
+            updates[0].revisions,
+            vec![p.tip.to_string(), format!("^{}", p.base)]
+        );
+    }
+
+    #[test]
+    fn pre_push_of_new_ref_excludes_remote_refs() {
+        let p = Pushed::new();
+        let updates = p
+            .pre_push(&format!(
+                "refs/heads/topic {} refs/heads/topic {}\n",
+                p.tip, ZERO
+            ))
+            .unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].name, "refs/heads/topic");
+        assert_eq!(
+            updates[0].revisions,
+            vec![p.tip.to_string(), format!("^{}", p.base)]
+        );
+    }
+
+    #[test]
+    fn pre_push_of_unknown_remote_commit_is_a_new_ref() {
+        let p = Pushed::new();
+        let unknown = "1111111111111111111111111111111111111111";
+        let updates = p
+            .pre_push(&format!(
+                "refs/heads/master {} refs/heads/master {}\n",
+                p.tip, unknown
+            ))
+            .unwrap();
+        assert_eq!(
+            updates[0].revisions,
+            vec![p.tip.to_string(), format!("^{}", p.base)]
+        );
+    }
+
+    #[test]
+    fn pre_push_of_deleted_ref() {
+        let p = Pushed::new();
+        let updates = p
+            .pre_push(&format!("(delete) {} refs/heads/topic {}\n", ZERO, p.tip))
+            .unwrap();
+        assert!(updates.is_empty());
+    }
+
+    #[test]
+    fn pre_push_rejects_malformed_input() {
+        let p = Pushed::new();
+        assert!(p.pre_push("refs/heads/master\n").is_err());
+        assert!(p.pre_push("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn pre_receive_of_updated_ref() {
+        let p = Pushed::new();
+        let input = format!("{} {} refs/heads/master\n", p.base, p.tip);
+        let updates = pre_receive_updates(&p.repo.repository, input.as_bytes()).unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].name, "refs/heads/master");
+        assert_eq!(
+            updates[0].revisions,
+            vec![p.tip.to_string(), format!("^{}", p.base)]
+        );
+    }
+
+    #[test]
+    fn received_new_ref_excludes_existing_refs() {
+        let p = Pushed::new();
+        let update = received_update(
+            &p.repo.repository,
+            "refs/heads/topic",
+            ZERO,
+            &p.tip.to_string(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(update.name, "refs/heads/topic");
+        assert_eq!(
+            update.revisions,
+            vec![p.tip.to_string(), format!("^{}", p.base)]
+        );
+    }
+
+    #[test]
+    fn received_deletion_has_no_update() {
+        let p = Pushed::new();
+        let update = received_update(
+            &p.repo.repository,
+            "refs/heads/topic",
+            &p.tip.to_string(),
+            ZERO,
+        )
+        .unwrap();
+        assert!(update.is_none());
+        let input = format!("{} {} refs/heads/topic\n", p.tip, ZERO);
+        let updates = pre_receive_updates(&p.repo.repository, input.as_bytes()).unwrap();
+        assert!(updates.is_empty());
+    }
+
+    #[test]
+    fn pre_receive_rejects_malformed_input() {
+        let p = Pushed::new();
+        let input = format!("{} refs/heads/master\n", p.tip);
+        assert!(pre_receive_updates(&p.repo.repository, input.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use structopt::StructOpt;
+
+use crate::baseline;
+use crate::commands::fetch;
+use crate::index;
+use crate::local;
+use crate::prelude::*;
+use crate::reviews;
+use crate::revision;
+use crate::shared;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Log {
+    #[structopt(flatten)]
+    pub revision_range: revision::RevisionRange,
+
+    /// Show each commit on a single line
+    #[structopt(long = "oneline")]
+    pub oneline: bool,
+
+    /// Draw the commit graph next to the log
+    #[structopt(long = "graph")]
+    pub graph: bool,
+
+    /// Only show commits whose author name or email contains the given text
+    #[structopt(long = "author")]
+    pub author: Option<String>,
+
+    /// Only show commits changing paths under the given path or matching the given glob
+    #[structopt(long = "path")]
+    pub paths: Vec<String>,
+
+    #[structopt(flatten)]
+    pub trust_params: fetch::TrustDistanceParams,
+}
+
+/// Run 'log' subcommand.
+pub fn run_command(args: &Log) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let index = index::Index::load(&local.index_path)?;
+    let db = reviews::ReviewDb::load(&local.repository, &args.trust_params.clone().into())?;
+
+    let mut revisions = if args.revision_range.revisions.is_empty() {
+        vec!["HEAD".to_string()]
+    } else {
+        args.revision_range.revisions.clone()
+    };
+    revisions.extend(baseline::exclusions(baseline::load(&local)?));
+    let mut commits = vec![];
+    for commit in revision::walk(
+        &local.repository,
+        &revisions,
+        &args.revision_range.walk_options,
+    )? {
+        if is_shown(&local.repository, &commit, args)? {
+            commits.push(commit);
+        }
+    }
+
+    let shown: HashSet<git2::Oid> = commits.iter().map(git2::Commit::id).collect();
+    let mut graph = Graph::default();
+    for commit in &commits {
+        let commit_id = commit.id().to_string();
+        let label = review_label(
+            &db.summary(&commit_id),
+            index.contains_commit_id(&commit_id),
+        );
+        let (row, continuation) = if args.graph {
+            graph.row(commit, &shown)
+        } else {
+            ("".into(), "".into())
+        };
+
+        if args.oneline {
+            let short_id: String = commit_id.chars().take(8).collect();
+            println!(
+                "{}{} {} {}",
+                row,
+                short_id,
+                label,
+                commit.summary().unwrap_or("")
+            );
+            continue;
+        }
+
+        let author = commit.author();
+        println!("{}commit {}", row, commit_id);
+        println!(
+            "{}Author:  {} <{}>",
+            continuation,
+            author.name().unwrap_or(""),
+            author.email().unwrap_or("")
+        );
+        println!(
+            "{}Date:    {}",
+            continuation,
+            shared::format_git_time(&author.when())
+        );
+        println!("{}Reviews: {}", continuation, label);
+        println!("{}", continuation);
+        for line in commit.message().unwrap_or("").trim_end().lines() {
+            println!("{}    {}", continuation, line);
+        }
+        println!("{}", continuation);
+    }
+    Ok(())
+}
+
+/// Whether a commit passes the author and path filters.
+fn is_shown(repository: &git2::Repository, commit: &git2::Commit, args: &Log) -> Result<bool> {
+    if let Some(author) = &args.author {
+        let signature = commit.author();
+        let name = signature.name().unwrap_or("");
+        let email = signature.email().unwrap_or("");
+        if !name.contains(author.as_str()) && !email.contains(author.as_str()) {
+            return Ok(false);
+        }
+    }
+
+    if args.paths.is_empty() {
+        return Ok(true);
+    }
+    let changed_paths = shared::changed_paths(repository, commit)?;
+    for filter in &args.paths {
+        for path in &changed_paths {
+            if shared::path_matches(filter, path)? {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Describe the review state of a commit in a few words.
+fn review_label(summary: &reviews::ReviewSummary, staged: bool) -> String {
+    let mut label = format!("{} trusted review(s)", summary.trusted);
+    if let Some(rating) = &summary.best_rating {
+        label += &format!(", best rating {}", rating);
+    }
+    if summary.distrusted {
+        label += ", DISTRUSTED";
+    }
+    if staged {
+        label += ", staged";
+    }
+    format!("[{}]", label)
+}
+
+/// Lanes of a simple text commit graph.
+///
+/// Each lane holds the commit expected next in it; commits must be added in
+/// topological order.
+#[derive(Default)]
+struct Graph {
+    lanes: Vec<Option<git2::Oid>>,
+}
+
+impl Graph {
+    /// Graph prefix of the row for a commit, and of any line following it.
+    fn row(&mut self, commit: &git2::Commit, shown: &HashSet<git2::Oid>) -> (String, String) {
+        let id = commit.id();
+        let column = match self.lanes.iter().position(|lane| *lane == Some(id)) {
+            Some(column) => column,
+            None => self.free_lane(),
+        };
+
+        let mut row = String::new();
+        for (i, lane) in self.lanes.iter().enumerate() {
+            row += if i == column {
+                "* "
+            } else if lane.is_some() {
+                "| "
+            } else {
+                "  "
+            };
+        }
+
+        // Lanes waiting for this commit end here.
+        for lane in self.lanes.iter_mut() {
+            if *lane == Some(id) {
+                *lane = None;
+            }
+        }
+        let parents: Vec<git2::Oid> = commit
+            .parent_ids()
+            .filter(|parent| shown.contains(parent))
+            .collect();
+        for (i, parent) in parents.iter().enumerate() {
+            if self.lanes.contains(&Some(*parent)) {
+                continue;
+            }
+            let lane = if i == 0 { column } else { self.free_lane() };
+            self.lanes[lane] = Some(*parent);
+        }
+        while self.lanes.last() == Some(&None) {
+            self.lanes.pop();
+        }
+
+        let continuation = self
+            .lanes
+            .iter()
+            .map(|lane| if lane.is_some() { "| " } else { "  " })
+            .collect();
+        (row, continuation)
+    }
+
+    fn free_lane(&mut self) -> usize {
+        match self.lanes.iter().position(Option::is_none) {
+            Some(lane) => lane,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+}
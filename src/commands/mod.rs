@@ -3,11 +3,20 @@ use structopt::StructOpt;
 use crate::prelude::*;
 
 mod add;
+mod baseline;
+mod blame;
+mod commit;
+mod coverage;
 mod fetch;
+mod hooks;
 mod id;
 mod import;
+mod log;
 mod publish;
+mod reset;
+mod show;
 mod status;
+mod verify;
 
 pub fn run_command(command: Command) -> Result<()> {
     match command {
@@ -29,6 +38,33 @@ pub fn run_command(command: Command) -> Result<()> {
         Command::Status => {
             status::run_command()?;
         }
+        Command::Reset(args) => {
+            reset::run_command(&args)?;
+        }
+        Command::Commit(args) => {
+            commit::run_command(&args)?;
+        }
+        Command::Verify(args) => {
+            verify::run_command(&args)?;
+        }
+        Command::Log(args) => {
+            log::run_command(&args)?;
+        }
+        Command::Blame(args) => {
+            blame::run_command(&args)?;
+        }
+        Command::Coverage(args) => {
+            coverage::run_command(&args)?;
+        }
+        Command::Show(args) => {
+            show::run_command(&args)?;
+        }
+        Command::Hooks(subcommand) => {
+            hooks::run_command(subcommand)?;
+        }
+        Command::Baseline(args) => {
+            baseline::run_command(&args)?;
+        }
     }
 
     Ok(())
@@ -57,8 +93,44 @@ pub enum Command {
     #[structopt(name = "add")]
     Add(add::Add),
 
+    /// Unstage commits
+    #[structopt(name = "reset", alias = "rm")]
+    Reset(reset::Reset),
+
     #[structopt(name = "status")]
     Status,
+
+    /// Create signed review proofs for the staged commits
+    #[structopt(name = "commit")]
+    Commit(commit::Commit),
+
+    /// Verify that commits have been reviewed by trusted Ids
+    #[structopt(name = "verify")]
+    Verify(verify::Verify),
+
+    /// Show commit history with the review state of each commit
+    #[structopt(name = "log")]
+    Log(log::Log),
+
+    /// Show the review state of the commit which last changed each line of a file
+    #[structopt(name = "blame")]
+    Blame(blame::Blame),
+
+    /// Report the share of reviewed commits and lines by directory, author and language
+    #[structopt(name = "coverage")]
+    Coverage(coverage::Coverage),
+
+    /// Show all known reviews of a commit
+    #[structopt(name = "show")]
+    Show(show::Show),
+
+    /// Install or remove Git hooks refusing to push unreviewed commits
+    #[structopt(name = "hooks")]
+    Hooks(hooks::Hooks),
+
+    /// Accept the history of a commit without review
+    #[structopt(name = "baseline")]
+    Baseline(baseline::Baseline),
 }
 
 #[derive(Debug, StructOpt, Clone)]
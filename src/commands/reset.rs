@@ -0,0 +1,64 @@
+use structopt::StructOpt;
+
+use crate::index;
+use crate::local;
+use crate::prelude::*;
+use crate::revision;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Reset {
+    /// Git revisions and ranges (e.g. `HEAD`, `A..B`, `A...B`, `^A B`)
+    #[structopt(name = "revision range")]
+    pub revisions: Vec<String>,
+
+    #[structopt(flatten)]
+    pub walk_options: revision::WalkOptions,
+
+    /// Unstage all commits
+    #[structopt(long = "all", short = "a")]
+    pub all: bool,
+
+    /// Unstage the snapshot of the given revision
+    #[structopt(long = "snapshot")]
+    pub snapshot: Option<String>,
+}
+
+/// Run 'reset' subcommand.
+pub fn run_command(args: &Reset) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let mut index = index::Index::load(&local.index_path)?;
+
+    if args.all {
+        index.clear();
+    } else if !args.revisions.is_empty() || args.snapshot.is_some() {
+        let commits = revision::commits(&local.repository, &args.revisions, &args.walk_options)?;
+        for commit in commits {
+            if index.remove(&commit.id().to_string()) {
+                let short_id: String = commit.id().to_string().chars().take(8).collect();
+                println!("Unstaged {}  {}", short_id, commit.summary().unwrap_or(""));
+            }
+        }
+        if let Some(revision) = &args.snapshot {
+            let commit = local
+                .repository
+                .revparse_single(revision)?
+                .peel_to_commit()?;
+            if index.remove(&index::snapshot_key(&commit.id().to_string())) {
+                let short_id: String = commit.id().to_string().chars().take(8).collect();
+                println!(
+                    "Unstaged snapshot {}  {}",
+                    short_id,
+                    commit.summary().unwrap_or("")
+                );
+            }
+        }
+    } else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Either a revision range, --snapshot or --all must be specified.",
+        ))?;
+    }
+
+    index.dump(&local.index_path)?;
+    Ok(())
+}
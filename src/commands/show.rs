@@ -0,0 +1,115 @@
+use structopt::StructOpt;
+
+use crate::commands::fetch;
+use crate::index;
+use crate::local;
+use crate::prelude::*;
+use crate::reviews;
+use crate::term;
+use crate::verification;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Show {
+    /// Commit to show the reviews of
+    #[structopt(default_value = "HEAD")]
+    pub commit: String,
+
+    #[structopt(flatten)]
+    pub trust_params: fetch::TrustDistanceParams,
+}
+
+/// Run 'show' subcommand.
+///
+/// Lists every known review of a commit, and of commits equivalent to it,
+/// along with its staged state and its status under the review policy.
+pub fn run_command(args: &Show) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let repository = &local.repository;
+    let index = index::Index::load(&local.index_path)?;
+    let verifier = verification::Verifier::load(repository, &args.trust_params.clone().into())?;
+    let commit = repository.revparse_single(&args.commit)?.peel_to_commit()?;
+    let commit_id = commit.id().to_string();
+
+    let mut markdown = format!(
+        "# Commit {}\n\n**{}**\n\n",
+        commit_id,
+        commit.summary().unwrap_or("")
+    );
+    markdown += &format!("* Status: *{}*\n", verifier.evaluate(&commit)?);
+    match index.get(&commit_id) {
+        Some(entry) => {
+            markdown += &format!(
+                "* Staged: rating {}, thoroughness {}, understanding {}\n",
+                entry.review.rating, entry.review.thoroughness, entry.review.understanding
+            );
+            markdown += &quote(&entry.comment);
+        }
+        None => markdown += "* Not staged\n",
+    }
+
+    markdown += "\n## Reviews\n\n";
+    let mut direct: Vec<&reviews::TrustedReview> =
+        verifier.db.reviews_of(&commit_id).iter().collect();
+    direct.sort_by_key(|r| r.review.common.date);
+    if direct.is_empty() {
+        markdown += "No known reviews.\n";
+    }
+    for review in direct {
+        markdown += &render_review(review, None);
+    }
+
+    let mut equivalent = verifier.equivalent_reviews(&commit)?;
+    equivalent.sort_by_key(|(review, _)| review.review.common.date);
+    if !equivalent.is_empty() {
+        markdown += "\n## Reviews of equivalent commits\n\n";
+        for (review, equivalence) in equivalent {
+            markdown += &render_review(review, Some(equivalence));
+        }
+    }
+
+    if term::Term::new().stdout_is_tty {
+        termimad::MadSkin::default().print_text(&markdown);
+    } else {
+        print!("{}", markdown);
+    }
+    Ok(())
+}
+
+fn render_review(
+    review: &reviews::TrustedReview,
+    equivalence: Option<verification::Equivalence>,
+) -> String {
+    let r = &review.review;
+    let distance = match review.distance {
+        Some(distance) => format!("distance {}", distance),
+        None => "not in the trust graph".into(),
+    };
+    let mut out = format!("### {}\n\n", r.common.from.id);
+    if let Some(equivalence) = equivalence {
+        let short_id: String = r.commit.id.chars().take(8).collect();
+        out += &format!(
+            "* **Equivalent commit** {} ({}): {}\n",
+            short_id, equivalence, r.commit.summary
+        );
+    }
+    out += &format!("* Trust: {} ({})\n", review.trust_level, distance);
+    out += &format!("* Date: {}\n", r.common.date);
+    if !r.paths.is_empty() {
+        out += &format!("* Paths: {}\n", r.paths.join(", "));
+    }
+    out += &format!(
+        "* Rating: *{}*, thoroughness {}, understanding {}\n",
+        r.review.rating, r.review.thoroughness, r.review.understanding
+    );
+    out += &quote(&r.comment);
+    out + "\n"
+}
+
+/// A comment as a Markdown block quote.
+fn quote(comment: &str) -> String {
+    comment
+        .trim()
+        .lines()
+        .map(|line| format!("> {}\n", line))
+        .collect()
+}
@@ -1,36 +1,47 @@
+use crate::baseline;
 use crate::index;
 use crate::local;
+use crate::policy;
 use crate::prelude::*;
+use crate::shared;
 
 pub fn run_command() -> Result<()> {
     let local = local::Local::auto_create_or_open()?;
     let index = index::Index::load(&local.index_path)?;
+    let policy = policy::Policy::load(&local.repository)?;
+    let baseline = baseline::load(&local)?;
     println!(
         "Commits staged as part of an ongoing review.\n\
          \t(use \"git crev commit\" to commit the review)\n"
     );
 
-    if !index.trust.is_empty() {
-        println!("Trusted:\n");
-        print_commits(&index.trust);
-        println!("\n");
-    }
-
-    if !index.distrust.is_empty() {
-        println!("Distrusted:\n");
-        print_commits(&index.distrust);
-        println!();
+    if index.is_empty() {
+        println!("No commits staged.");
+    } else {
+        print_entries(&index, &local, &policy, baseline)?;
     }
 
-    if index.trust.is_empty() && index.distrust.is_empty() {
-        println!("No commits staged.");
+    println!(
+        "\nReview policy: every commit needs {}.",
+        policy.base_requirement()
+    );
+    if let Some(baseline) = baseline {
+        println!(
+            "Baseline: commits reachable from {} are accepted.",
+            baseline
+        );
     }
 
     Ok(())
 }
 
-fn print_commits(index_entries: &std::collections::BTreeSet<index::IndexEntry>) {
-    for entry in index_entries {
+fn print_entries(
+    index: &index::Index,
+    local: &local::Local,
+    policy: &policy::Policy,
+    baseline: Option<git2::Oid>,
+) -> Result<()> {
+    for entry in index.entries() {
         let short_id: String = entry.commit_id.chars().take(8).collect();
 
         let truncate_length = 100;
@@ -39,6 +50,46 @@ fn print_commits(index_entries: &std::collections::BTreeSet<index::IndexEntry>)
         if entry.commit_summary.chars().count() > truncate_length {
             short_summary += "...";
         }
-        println!("\t{}  {}", short_id, short_summary);
+        if entry.snapshot {
+            short_summary = format!("(snapshot of all files) {}", short_summary);
+        }
+        println!(
+            "\t{}  {:<9} thoroughness={:<6} understanding={:<6}  {}",
+            short_id,
+            entry.review.rating,
+            entry.review.thoroughness,
+            entry.review.understanding,
+            short_summary
+        );
+        if let Some(comment) = entry.comment.lines().next() {
+            println!("\t          comment: {}", comment);
+        }
+        if !entry.paths.is_empty() {
+            println!("\t          paths: {}", entry.paths.join(", "));
+        }
+
+        // Path-specific policy requirements on top of the base requirement.
+        if entry.snapshot {
+            continue;
+        }
+        // The commit may be gone, e.g. after an amend or a rebase and gc.
+        let commit = match git2::Oid::from_str(&entry.commit_id)
+            .and_then(|id| local.repository.find_commit(id))
+        {
+            Ok(commit) => commit,
+            Err(_) => {
+                println!("\t          (commit not found in the repository)");
+                continue;
+            }
+        };
+        if baseline::contains(&local.repository, baseline, commit.id()) {
+            println!("\t          (already accepted by the baseline)");
+            continue;
+        }
+        let changed_paths = shared::changed_paths(&local.repository, &commit)?;
+        for requirement in policy.requirements(&changed_paths)?.iter().skip(1) {
+            println!("\t          (policy: needs {})", requirement);
+        }
     }
+    Ok(())
 }
@@ -0,0 +1,60 @@
+use structopt::StructOpt;
+
+use crate::baseline;
+use crate::commands::fetch;
+use crate::local;
+use crate::prelude::*;
+use crate::reviews;
+use crate::revision;
+use crate::verification;
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct Verify {
+    #[structopt(flatten)]
+    pub revision_range: revision::RevisionRange,
+
+    #[structopt(flatten)]
+    pub trust_params: fetch::TrustDistanceParams,
+}
+
+/// Run 'verify' subcommand.
+///
+/// Commits are evaluated against the repository's review policy, skipping
+/// those accepted by the baseline. Exits with a non-zero status if any commit
+/// in the range is not verified.
+pub fn run_command(args: &Verify) -> Result<()> {
+    let local = local::Local::auto_create_or_open()?;
+    let baseline = baseline::load(&local)?;
+    let mut commits = args.revision_range.commits(&local.repository)?;
+    commits.retain(|commit| !baseline::contains(&local.repository, baseline, commit.id()));
+    let verifier =
+        verification::Verifier::load(&local.repository, &args.trust_params.clone().into())?;
+
+    let mut failed = 0;
+    for commit in &commits {
+        let evaluation = verifier.evaluate(commit)?;
+        if evaluation.status != reviews::Status::Verified {
+            failed += 1;
+        }
+        let short_id: String = commit.id().to_string().chars().take(8).collect();
+        println!(
+            "{:<10}  {}  {}",
+            evaluation.status,
+            short_id,
+            commit.summary().unwrap_or("")
+        );
+        if evaluation.equivalence.is_some() {
+            println!("{:<10}  ({})", "", evaluation);
+        }
+    }
+
+    if failed > 0 {
+        eprintln!(
+            "\n{} of {} commit(s) failed verification.",
+            failed,
+            commits.len()
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! Rendering of commits and their diffs, in the style of `git show`.
+//!
+//! With colors, the lines of files in a few common languages are also syntax
+//! highlighted (see [`syntax`](../syntax/index.html)), with changed lines
+//! marked by their background.
+use std::io::prelude::*;
+
+use structopt::StructOpt;
+
+use crate::prelude::*;
+use crate::shared;
+use crate::syntax::Language;
+use crate::term;
+
+const RESET: &str = "\x1b[m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const REMOVED_BACKGROUND: &str = "\x1b[48;5;52m";
+const ADDED_BACKGROUND: &str = "\x1b[48;5;22m";
+
+/// Largest number of token pairs compared by `--word-diff`, bounding its memory
+/// use; larger blocks of changed lines are shown as whole lines.
+const MAX_WORD_DIFF_CELLS: usize = 1 << 22;
+
+/// Options controlling how diffs are computed and displayed.
+#[derive(Debug, StructOpt, Clone, Default)]
+pub struct DiffOptions {
+    /// Show changed words instead of whole changed lines
+    #[structopt(long = "word-diff")]
+    pub word_diff: bool,
+
+    /// Ignore whitespace when comparing lines
+    #[structopt(long = "ignore-all-space", short = "w")]
+    pub ignore_whitespace: bool,
+
+    /// Don't detect renamed files
+    #[structopt(long = "no-renames")]
+    pub no_renames: bool,
+
+    /// Don't pipe output into a pager
+    #[structopt(long = "no-pager")]
+    pub no_pager: bool,
+}
+
+impl DiffOptions {
+    /// Options for identifying a change rather than displaying it: whitespace
+    /// is significant and renames are not detected.
+    pub fn exact() -> Self {
+        Self {
+            no_renames: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Diff of a commit against its first parent.
+pub fn commit_diff<'a>(
+    repository: &'a git2::Repository,
+    commit: &git2::Commit,
+    options: &DiffOptions,
+) -> Result<git2::Diff<'a>> {
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.ignore_whitespace(options.ignore_whitespace);
+    let mut diff = repository.diff_tree_to_tree(
+        parent_tree.as_ref(),
+        Some(&commit.tree()?),
+        Some(&mut diff_options),
+    )?;
+    if !options.no_renames {
+        diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+    }
+    Ok(diff)
+}
+
+/// Render the header and patch of a commit, optionally with ANSI colors.
+pub fn render(
+    repository: &git2::Repository,
+    commit: &git2::Commit,
+    options: &DiffOptions,
+    color: bool,
+) -> Result<String> {
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut out = paint(YELLOW, &format!("commit {}", commit.id())) + "\n";
+    if commit.parent_count() > 1 {
+        let parents: Vec<String> = commit
+            .parent_ids()
+            .map(|id| id.to_string().chars().take(8).collect())
+            .collect();
+        out += &format!("Merge: {}\n", parents.join(" "));
+    }
+    let author = commit.author();
+    out += &format!(
+        "Author: {} <{}>\n",
+        author.name().unwrap_or(""),
+        author.email().unwrap_or("")
+    );
+    out += &format!("Date:   {}\n\n", shared::format_git_time(&author.when()));
+    for line in commit.message().unwrap_or("").trim_end().lines() {
+        out += &format!("    {}\n", line);
+    }
+    out += "\n";
+
+    let changed_line = |origin: char, content: &str, language: Option<&Language>| {
+        let content = content.trim_end_matches('\n');
+        let (style, background) = if origin == '-' {
+            (RED, REMOVED_BACKGROUND)
+        } else {
+            (GREEN, ADDED_BACKGROUND)
+        };
+        match language.filter(|_| color) {
+            Some(language) => format!(
+                "{}{}{}{}{}{}{}",
+                background,
+                style,
+                origin,
+                RESET,
+                background,
+                language.highlight(content, background),
+                RESET
+            ),
+            None => paint(style, &format!("{}{}", origin, content)),
+        }
+    };
+
+    let mut lines: Vec<(char, String, Option<&Language>)> = vec![];
+    commit_diff(repository, commit, options)?.print(
+        git2::DiffFormat::Patch,
+        |delta, _hunk, line| {
+            let content = String::from_utf8_lossy(line.content()).into_owned();
+            let path = delta.new_file().path().or_else(|| delta.old_file().path());
+            let language = path.and_then(Language::from_path);
+            lines.push((line.origin(), content, language));
+            true
+        },
+    )?;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let (origin, content, language) = &lines[i];
+        match origin {
+            'F' => out += &paint(BOLD, content.trim_end()),
+            'H' => out += &paint(CYAN, content.trim_end()),
+            '-' | '+' if options.word_diff => {
+                // Pair a block of removed lines with the added lines following it.
+                let removed_end = i + lines[i..].iter().take_while(|l| l.0 == '-').count();
+                let added_end = removed_end
+                    + lines[removed_end..]
+                        .iter()
+                        .take_while(|l| l.0 == '+')
+                        .count();
+                let removed: String = lines[i..removed_end].iter().map(|l| l.1.as_str()).collect();
+                let added: String = lines[removed_end..added_end]
+                    .iter()
+                    .map(|l| l.1.as_str())
+                    .collect();
+                match word_diff(&removed, &added, color) {
+                    Some(words) => out += &words,
+                    None => {
+                        let block: Vec<String> = lines[i..added_end]
+                            .iter()
+                            .map(|(origin, content, language)| {
+                                changed_line(*origin, content, *language)
+                            })
+                            .collect();
+                        out += &block.join("\n");
+                    }
+                }
+                out += "\n";
+                i = added_end;
+                continue;
+            }
+            '-' | '+' => out += &changed_line(*origin, content, *language),
+            ' ' => {
+                let content = content.trim_end_matches('\n');
+                match language.filter(|_| color) {
+                    Some(language) => out += &format!(" {}", language.highlight(content, "")),
+                    None => out += &format!(" {}", content),
+                }
+            }
+            _ => out += content.trim_end_matches('\n'),
+        }
+        out += "\n";
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// Split text into words and the whitespace between them.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let is_space = c.is_whitespace();
+        if in_space.map_or(false, |s| s != is_space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(is_space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Render the change from `removed` to `added` word by word, like `git diff --word-diff`.
+///
+/// Returns `None` if the change is too large to compare word by word.
+fn word_diff(removed: &str, added: &str, color: bool) -> Option<String> {
+    let old = tokenize(removed);
+    let new = tokenize(added);
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_WORD_DIFF_CELLS {
+        return None;
+    }
+
+    // Longest common subsequence of tokens.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mark_removed = |token: &str| {
+        if color {
+            format!("{}{}{}", RED, token, RESET)
+        } else {
+            format!("[-{}-]", token)
+        }
+    };
+    let mark_added = |token: &str| {
+        if color {
+            format!("{}{}{}", GREEN, token, RESET)
+        } else {
+            format!("{{+{}+}}", token)
+        }
+    };
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            out += old[i];
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            out += &mark_added(new[j]);
+            j += 1;
+        } else {
+            out += &mark_removed(old[i]);
+            i += 1;
+        }
+    }
+    Some(out.trim_end_matches('\n').to_string())
+}
+
+/// Diffstat of a commit, as printed by `git show --stat`.
+pub fn stat(
+    repository: &git2::Repository,
+    commit: &git2::Commit,
+    options: &DiffOptions,
+) -> Result<String> {
+    let stats = commit_diff(repository, commit, options)?.stats()?;
+    let buf = stats.to_buf(git2::DiffStatsFormat::FULL, 80)?;
+    Ok(buf.as_str().unwrap_or("").to_string())
+}
+
+/// Pager command, chosen with the same precedence as Git: `GIT_PAGER`,
+/// `core.pager`, `PAGER`, and finally `less`.
+fn pager_command(repository: &git2::Repository) -> String {
+    if let Ok(pager) = std::env::var("GIT_PAGER") {
+        return pager;
+    }
+    if let Ok(pager) = repository
+        .config()
+        .and_then(|config| config.get_string("core.pager"))
+    {
+        return pager;
+    }
+    std::env::var("PAGER").unwrap_or_else(|_| "less".into())
+}
+
+/// Print a commit with its diff, through the pager when writing to a terminal.
+pub fn show(
+    repository: &git2::Repository,
+    commit: &git2::Commit,
+    options: &DiffOptions,
+) -> Result<()> {
+    let term = term::Term::new();
+    let text = render(repository, commit, options, term.stdout_is_tty)?;
+
+    let pager = pager_command(repository);
+    if options.no_pager || !term.stdout_is_tty || pager.is_empty() || pager == "cat" {
+        print!("{}", text);
+        return Ok(());
+    }
+
+    // Like Git, let `less` quit on short output and pass colors through.
+    let mut child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&pager)
+        .env(
+            "LESS",
+            std::env::var("LESS").unwrap_or_else(|_| "FRX".into()),
+        )
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        // The pager may be closed before reading everything.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_diff_of_large_blocks_falls_back_to_lines() {
+        assert!(word_diff("a b\n", "a c\n", false).is_some());
+        let removed = "a\n".repeat(3000);
+        let added = "b\n".repeat(3000);
+        assert!(word_diff(&removed, &added, false).is_none());
+    }
+}
@@ -0,0 +1,74 @@
+//! Composing review comments in the user's editor.
+//!
+use std::io::prelude::*;
+
+use crate::diff;
+use crate::local;
+use crate::prelude::*;
+
+/// Editor command, chosen with the same precedence as Git: `GIT_EDITOR`,
+/// `core.editor`, `VISUAL`, `EDITOR`, and finally `vi`.
+fn editor_command(repository: &git2::Repository) -> String {
+    if let Ok(editor) = std::env::var("GIT_EDITOR") {
+        return editor;
+    }
+    if let Ok(editor) = repository
+        .config()
+        .and_then(|config| config.get_string("core.editor"))
+    {
+        return editor;
+    }
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".into())
+}
+
+/// Open the user's editor on `text` and return the edited text, without lines
+/// starting with `#`.
+pub fn edit(local: &local::Local, text: &str) -> Result<String> {
+    let file_path = local.root_path.join("COMMENT_EDITMSG");
+    std::fs::File::create(&file_path)?.write_all(text.as_bytes())?;
+
+    // Run through the shell, like Git does, so that editor commands with
+    // arguments (e.g. `code --wait`) work.
+    let editor = editor_command(&local.repository);
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(&file_path)
+        .status()?;
+    if !status.success() {
+        bail!("Editor `{}` exited with {}", editor, status);
+    }
+
+    let edited = std::fs::read_to_string(&file_path)?;
+    std::fs::remove_file(&file_path)?;
+    let comment: Vec<&str> = edited
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+    Ok(comment.join("\n").trim().to_string())
+}
+
+/// Let the user write the review comment of a commit in their editor.
+///
+/// The editor is prefilled with `comment` followed by the commit summary and
+/// diffstat as a commented-out template.
+pub fn edit_comment(local: &local::Local, commit: &git2::Commit, comment: &str) -> Result<String> {
+    let mut template = format!(
+        "{}\n\n\
+         # Please enter the review comment for commit {}.\n\
+         # Lines starting with '#' will be ignored.\n\
+         #\n\
+         # {}\n\
+         #\n",
+        comment,
+        commit.id(),
+        commit.summary().unwrap_or("")
+    );
+    for line in diff::stat(&local.repository, commit, &diff::DiffOptions::default())?.lines() {
+        template += &format!("# {}\n", line);
+    }
+    edit(local, &template)
+}
@@ -2,73 +2,130 @@ use serde;
 use std::io::prelude::*;
 
 use crate::prelude::*;
-use crev_lib as crev;
+use crate::shared;
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct IndexEntry {
     pub commit_id: String,
     pub commit_summary: String,
+    /// Stable patch ID of the commit, identifying the same change after a
+    /// rebase or cherry-pick.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch_id: Option<String>,
+    #[serde(flatten)]
+    pub review: crev_data::Review,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+    /// Whether the entry reviews all files of the commit's tree rather than
+    /// the changes it makes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub snapshot: bool,
+    /// Pathspecs the review is limited to; empty for the whole commit.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
 }
 
-impl Ord for IndexEntry {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.commit_id.cmp(&other.commit_id)
+impl IndexEntry {
+    pub fn new(
+        repository: &git2::Repository,
+        commit: &git2::Commit,
+        review: crev_data::Review,
+    ) -> Result<Self> {
+        Ok(Self {
+            commit_id: commit.id().to_string(),
+            commit_summary: commit.summary().unwrap_or("").to_string(),
+            patch_id: shared::patch_id(repository, commit)?.map(|id| id.to_string()),
+            review,
+            comment: "".into(),
+            snapshot: false,
+            paths: vec![],
+        })
     }
-}
 
-impl PartialOrd for IndexEntry {
-    fn partial_cmp(&self, other: &IndexEntry) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    /// Entry reviewing the whole tree of a commit.
+    pub fn new_snapshot(commit: &git2::Commit, review: crev_data::Review) -> Self {
+        Self {
+            commit_id: commit.id().to_string(),
+            commit_summary: commit.summary().unwrap_or("").to_string(),
+            patch_id: None,
+            review,
+            comment: "".into(),
+            snapshot: true,
+            paths: vec![],
+        }
+    }
+
+    /// Key of the entry in the index. A snapshot and a review of the changes
+    /// of the same commit can be staged together.
+    pub fn key(&self) -> String {
+        if self.snapshot {
+            snapshot_key(&self.commit_id)
+        } else {
+            self.commit_id.clone()
+        }
     }
 }
 
-impl Eq for IndexEntry {}
+/// Key of the snapshot of a commit in the index.
+pub fn snapshot_key(commit_id: &str) -> String {
+    format!("snapshot {}", commit_id)
+}
 
+/// Commits staged as part of an ongoing review, keyed by commit ID (see
+/// [`IndexEntry::key`]).
 #[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Index {
-    pub trust: std::collections::BTreeSet<IndexEntry>,
-    pub distrust: std::collections::BTreeSet<IndexEntry>,
-    all_commit_ids: std::collections::BTreeSet<String>,
+    entries: std::collections::BTreeMap<String, IndexEntry>,
 }
 
 impl Index {
-    /// Insert entries into the index. Ensures any existing duplicates are handled correctly.
-    pub fn insert(
-        &mut self,
-        new_entries: &mut std::collections::BTreeSet<IndexEntry>,
-        trust_status: &crev::TrustOrDistrust,
-    ) {
-        match trust_status {
-            crev::TrustOrDistrust::Trust => {
-                self.trust.append(new_entries);
-                let intersection: Vec<IndexEntry> =
-                    self.trust.intersection(&self.distrust).cloned().collect();
-                for element in intersection {
-                    self.distrust.remove(&element);
-                }
-            }
-            crev::TrustOrDistrust::Distrust => {
-                self.distrust.append(new_entries);
-                let intersection: Vec<IndexEntry> =
-                    self.distrust.intersection(&self.trust).cloned().collect();
-                for element in intersection {
-                    self.distrust.remove(&element);
-                }
-            }
-        };
-
-        for new_entry in new_entries.iter() {
-            self.all_commit_ids.insert(new_entry.commit_id.clone());
-        }
+    /// Insert an entry into the index, replacing any existing entry for the same commit.
+    pub fn insert(&mut self, entry: IndexEntry) {
+        self.entries.insert(entry.key(), entry);
+    }
+
+    pub fn contains_commit_id(&self, commit_id: &str) -> bool {
+        self.entries.contains_key(commit_id)
+    }
+
+    pub fn get(&self, commit_id: &str) -> Option<&IndexEntry> {
+        self.entries.get(commit_id)
+    }
+
+    /// Remove an entry by its key, i.e. the commit ID for reviews of commits.
+    /// Returns whether it was staged.
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    /// Remove all staged entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
-    pub fn contains_commit_id(&self, commit_id: String) -> bool {
-        self.all_commit_ids.contains(&commit_id)
+    /// All staged entries, ordered by commit ID.
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values()
     }
 
+    /// Load the index, converting an index written by an older version that
+    /// only staged commits as trusted or distrusted.
     pub fn load(file_path: &std::path::PathBuf) -> Result<Self> {
         let contents = std::fs::read_to_string(&file_path).unwrap_or("".to_owned());
-        Ok(serde_yaml::from_str(&contents)?)
+        if contents.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        match serde_yaml::from_str(&contents) {
+            Ok(index) => Ok(index),
+            Err(e) => match serde_yaml::from_str::<LegacyIndex>(&contents) {
+                Ok(legacy) => Ok(legacy.into()),
+                Err(_) => Err(e.into()),
+            },
+        }
     }
 
     pub fn dump(&self, file_path: &std::path::PathBuf) -> Result<()> {
@@ -77,3 +134,89 @@ impl Index {
         Ok(())
     }
 }
+
+/// Index of older versions, staging commits as trusted or distrusted.
+#[derive(serde::Deserialize)]
+struct LegacyIndex {
+    trust: Vec<LegacyIndexEntry>,
+    distrust: Vec<LegacyIndexEntry>,
+}
+
+#[derive(serde::Deserialize)]
+struct LegacyIndexEntry {
+    commit_id: String,
+    commit_summary: String,
+}
+
+impl From<LegacyIndex> for Index {
+    /// Trusted commits become positive reviews and distrusted ones negative
+    /// reviews, with the default levels of `git crev add`.
+    fn from(legacy: LegacyIndex) -> Self {
+        let mut index = Self::default();
+        let staged = legacy
+            .trust
+            .into_iter()
+            .map(|entry| (entry, crev_data::Rating::Positive))
+            .chain(
+                legacy
+                    .distrust
+                    .into_iter()
+                    .map(|entry| (entry, crev_data::Rating::Negative)),
+            );
+        for (entry, rating) in staged {
+            index.insert(IndexEntry {
+                commit_id: entry.commit_id,
+                commit_summary: entry.commit_summary,
+                patch_id: None,
+                review: crev_data::Review {
+                    thoroughness: crev_data::Level::Low,
+                    understanding: crev_data::Level::Medium,
+                    rating,
+                },
+                comment: "".into(),
+                snapshot: false,
+                paths: vec![],
+            });
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_legacy_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_path = dir.path().join("index");
+        let (trusted, distrusted) = ("a".repeat(40), "b".repeat(40));
+        std::fs::write(
+            &index_path,
+            format!(
+                "---\n\
+                 trust:\n  - commit_id: {trusted}\n    commit_summary: Add a\n\
+                 distrust:\n  - commit_id: {distrusted}\n    commit_summary: Add b\n\
+                 all_commit_ids:\n  - {trusted}\n  - {distrusted}\n",
+                trusted = trusted,
+                distrusted = distrusted
+            ),
+        )
+        .unwrap();
+
+        let index = Index::load(&index_path).unwrap();
+        let entries: Vec<&IndexEntry> = index.entries().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].commit_id, trusted);
+        assert_eq!(entries[0].commit_summary, "Add a");
+        assert_eq!(entries[0].review.rating, crev_data::Rating::Positive);
+        assert_eq!(entries[0].review.thoroughness, crev_data::Level::Low);
+        assert_eq!(entries[0].review.understanding, crev_data::Level::Medium);
+        assert_eq!(entries[1].commit_id, distrusted);
+        assert_eq!(entries[1].review.rating, crev_data::Rating::Negative);
+
+        // The converted index is written back in the current format.
+        index.dump(&index_path).unwrap();
+        assert_eq!(Index::load(&index_path).unwrap(), index);
+    }
+}
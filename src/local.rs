@@ -7,6 +7,7 @@ use crate::prelude::*;
 pub struct Local {
     pub root_path: std::path::PathBuf,
     pub index_path: std::path::PathBuf,
+    pub baseline_path: std::path::PathBuf,
     pub repository: git2::Repository,
 }
 
@@ -17,16 +18,15 @@ impl Local {
             std::io::ErrorKind::NotFound,
             "Current working directory does not seem to be within a Git repository.",
         )))?;
-        let root_path = repository
-            .workdir()
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Could not find Git working directory.",
-            ))?
-            .join(".crev");
+        let root_path = match repository.workdir() {
+            Some(workdir) => workdir.join(".crev"),
+            // Bare repositories, e.g. on a server, keep it in the Git directory.
+            None => repository.path().join("crev"),
+        };
         Ok(Self {
             root_path: root_path.clone(),
             index_path: root_path.join("index").into(),
+            baseline_path: root_path.join("baseline"),
             repository: repository.into(),
         })
     }
@@ -35,7 +35,9 @@ impl Local {
         let local = Self::new()?;
         if !local.root_path.exists() {
             std::fs::create_dir_all(&local.root_path)?;
-            modify_git_exclude(&local.repository)?;
+            if !local.repository.is_bare() {
+                modify_git_exclude(&local.repository)?;
+            }
         }
         Ok(local)
     }
@@ -13,12 +13,25 @@ use structopt::StructOpt;
 // /// Documentation
 // pub mod doc;
 
+mod baseline;
 mod commands;
+mod diff;
+mod editor;
 mod index;
 mod local;
+mod notes;
+mod policy;
 mod prelude;
+mod proof;
+mod remote;
+mod reviews;
+mod revision;
 mod shared;
+mod syntax;
 mod term;
+#[cfg(test)]
+mod testing;
+mod verification;
 
 fn main() {
     env_logger::init();
@@ -0,0 +1,116 @@
+//! Storage of commit review proofs as Git notes.
+//!
+//! Proofs are attached to the reviewed commits under a dedicated notes ref, so
+//! that they travel with the repository (`git push origin refs/notes/crev`)
+//! and show up in `git log --notes=crev`. A note holds the proofs of every
+//! reviewer of its commit, one after the other.
+use crev_data::proof::Proof;
+
+use crate::prelude::*;
+
+/// Notes ref used unless `crev.notesRef` is configured.
+pub const DEFAULT_REF: &str = "refs/notes/crev";
+
+/// Notes ref holding commit review proofs, from the `crev.notesRef` Git
+/// configuration.
+pub fn notes_ref(repository: &git2::Repository) -> String {
+    repository
+        .config()
+        .and_then(|config| config.get_string("crev.notesRef"))
+        .unwrap_or_else(|_| DEFAULT_REF.into())
+}
+
+/// Add a proof to the note of a commit, keeping the proofs already there.
+pub fn store(repository: &git2::Repository, commit_id: git2::Oid, proof: &Proof) -> Result<()> {
+    let notes_ref = notes_ref(repository);
+    let mut note = match repository.find_note(Some(&notes_ref), commit_id) {
+        Ok(note) => note.message().unwrap_or("").to_string(),
+        Err(_) => String::new(),
+    };
+    let proof = proof.to_string();
+    if note.contains(&proof) {
+        return Ok(());
+    }
+    if !note.is_empty() && !note.ends_with('\n') {
+        note.push('\n');
+    }
+    note += &proof;
+
+    let signature = repository.signature()?;
+    repository.note(
+        &signature,
+        &signature,
+        Some(&notes_ref),
+        commit_id,
+        &note,
+        true,
+    )?;
+    Ok(())
+}
+
+/// All proofs stored in notes.
+pub fn proofs(repository: &git2::Repository) -> Result<Vec<Proof>> {
+    proofs_in(repository, &notes_ref(repository))
+}
+
+/// All proofs stored in the notes of a given notes ref.
+///
+/// Notes that can't be parsed are skipped with a warning, so that a single
+/// malformed note doesn't hide all reviews.
+pub fn proofs_in(repository: &git2::Repository, notes_ref: &str) -> Result<Vec<Proof>> {
+    let notes = match repository.notes(Some(notes_ref)) {
+        Ok(notes) => notes,
+        Err(ref e) if e.code() == git2::ErrorCode::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut proofs = vec![];
+    for note in notes {
+        let (note_id, commit_id) = note?;
+        let blob = repository.find_blob(note_id)?;
+        match Proof::parse_from(blob.content()) {
+            Ok(parsed) => proofs.extend(parsed),
+            Err(e) => eprintln!(
+                "Ignoring unparsable proofs in the {} note of {}: {}",
+                notes_ref, commit_id, e
+            ),
+        }
+    }
+    Ok(proofs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof;
+    use crate::testing::{self, TestRepo};
+
+    #[test]
+    fn skips_malformed_notes() {
+        let repo = TestRepo::new();
+        let reviewed = repo.commit(&[], &[("a.txt", "a\n")], "Add a");
+        let other = repo.commit(&[reviewed], &[("b.txt", "b\n")], "Add b");
+
+        let (_, proof) = testing::signed_review(&repo, reviewed, &testing::id(), |_| {});
+        store(&repo.repository, reviewed, &proof).unwrap();
+
+        let signature = repo.repository.signature().unwrap();
+        repo.repository
+            .note(
+                &signature,
+                &signature,
+                Some(DEFAULT_REF),
+                other,
+                "-----BEGIN CREV PROOF-----\nnot a proof\n",
+                false,
+            )
+            .unwrap();
+
+        let proofs = proofs(&repo.repository).unwrap();
+        assert_eq!(proofs.len(), 1);
+        let parsed = proof::CommitReview::from_proof(&proofs[0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.commit.id, reviewed.to_string());
+    }
+}
@@ -0,0 +1,365 @@
+//! Repository review policy.
+//!
+//! The policy lives in `.crev-policy.yaml` at the repository root. It is read
+//! from the committed tree rather than the working directory, so that changes to
+//! the policy itself only take effect once they have been committed (and can be
+//! reviewed like any other change). Example:
+//!
+//! ```yaml
+//! min-reviews: 2
+//! min-trust-level: medium
+//! distrust-veto: true
+//! paths:
+//!   - glob: "src/crypto/**"
+//!     min-reviews: 1
+//!     min-trust-level: high
+//! ```
+use std::{collections::HashSet, path::PathBuf};
+
+use crev_data::TrustLevel;
+use serde;
+
+use crate::prelude::*;
+use crate::proof;
+use crate::reviews::{Status, TrustedReview};
+
+pub const POLICY_FILE_NAME: &str = ".crev-policy.yaml";
+
+/// Number of reviews, and the trust level their authors must have at least.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Requirement {
+    pub min_reviews: usize,
+    pub min_trust_level: TrustLevel,
+}
+
+impl std::fmt::Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} review(s) at trust level {} or above",
+            self.min_reviews, self.min_trust_level
+        )
+    }
+}
+
+/// Stricter requirement for commits changing paths matching a glob.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PathRule {
+    pub glob: String,
+    pub min_reviews: Option<usize>,
+    pub min_trust_level: Option<TrustLevel>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default = "default_min_reviews")]
+    pub min_reviews: usize,
+    #[serde(default = "default_min_trust_level")]
+    pub min_trust_level: TrustLevel,
+    /// Whether a distrust review from any trusted Id rejects a commit.
+    #[serde(default = "default_distrust_veto")]
+    pub distrust_veto: bool,
+    #[serde(default)]
+    pub paths: Vec<PathRule>,
+}
+
+fn default_min_reviews() -> usize {
+    1
+}
+
+fn default_min_trust_level() -> TrustLevel {
+    TrustLevel::Low
+}
+
+fn default_distrust_veto() -> bool {
+    true
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            min_reviews: default_min_reviews(),
+            min_trust_level: default_min_trust_level(),
+            distrust_veto: default_distrust_veto(),
+            paths: vec![],
+        }
+    }
+}
+
+impl Policy {
+    /// Load the policy committed at `HEAD`, or the default policy if there is none.
+    pub fn load(repository: &git2::Repository) -> Result<Self> {
+        let tree = match repository.head().and_then(|head| head.peel_to_tree()) {
+            Ok(tree) => tree,
+            Err(_) => return Ok(Self::default()),
+        };
+        let entry = match tree.get_name(POLICY_FILE_NAME) {
+            Some(entry) => entry,
+            None => return Ok(Self::default()),
+        };
+        let blob = entry.to_object(repository)?.peel_to_blob()?;
+        let policy = serde_yaml::from_slice(blob.content())
+            .with_context(|_| format_err!("Could not parse {}", POLICY_FILE_NAME))?;
+        Ok(policy)
+    }
+
+    /// Requirement applying to every commit.
+    pub fn base_requirement(&self) -> Requirement {
+        Requirement {
+            min_reviews: self.min_reviews,
+            min_trust_level: self.min_trust_level,
+        }
+    }
+
+    /// All requirements applying to a commit changing the given paths.
+    pub fn requirements(&self, changed_paths: &[PathBuf]) -> Result<Vec<Requirement>> {
+        let mut requirements = vec![self.base_requirement()];
+        for rule in &self.paths {
+            let pattern = glob::Pattern::new(&rule.glob)
+                .with_context(|_| format_err!("Invalid glob in policy: {}", rule.glob))?;
+            if changed_paths.iter().any(|path| pattern.matches_path(path)) {
+                requirements.push(Requirement {
+                    min_reviews: rule.min_reviews.unwrap_or(self.min_reviews),
+                    min_trust_level: rule.min_trust_level.unwrap_or(self.min_trust_level),
+                });
+            }
+        }
+        Ok(requirements)
+    }
+
+    /// Evaluate the reviews of a commit changing the given paths.
+    ///
+    /// Every changed path must satisfy the requirements applying to it with
+    /// reviews covering it, so that reviews limited to some paths of the
+    /// commit can together cover all of it.
+    pub fn evaluate(&self, reviews: &[TrustedReview], changed_paths: &[PathBuf]) -> Result<Status> {
+        let distrusted = reviews.iter().any(|r| {
+            r.trust_level > TrustLevel::None && proof::is_distrust(&r.review.review.rating)
+        });
+        if distrusted && self.distrust_veto {
+            return Ok(Status::Distrusted);
+        }
+
+        let mut satisfied = true;
+        if changed_paths.is_empty() {
+            satisfied = is_satisfied(&self.base_requirement(), reviews, None);
+        }
+        for path in changed_paths {
+            for requirement in self.requirements(std::slice::from_ref(path))? {
+                satisfied &= is_satisfied(&requirement, reviews, Some(path));
+            }
+        }
+        Ok(if satisfied {
+            Status::Verified
+        } else if distrusted {
+            Status::Distrusted
+        } else {
+            Status::Unreviewed
+        })
+    }
+}
+
+/// Whether enough distinct, sufficiently trusted reviewers vouch for a change,
+/// or for the given path of it.
+fn is_satisfied(
+    requirement: &Requirement,
+    reviews: &[TrustedReview],
+    path: Option<&std::path::Path>,
+) -> bool {
+    let reviewers: HashSet<_> = reviews
+        .iter()
+        .filter(|r| proof::is_trust(&r.review.review.rating))
+        .filter(|r| r.trust_level >= requirement.min_trust_level)
+        // Only look at the paths of reviews which could count at all.
+        .filter(|r| path.map_or(true, |path| r.review.covers(path)))
+        .map(|r| &r.review.common.from.id)
+        .collect();
+    reviewers.len() >= requirement.min_reviews
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crev_data::Rating;
+
+    fn review(
+        from: &crev_data::id::UnlockedId,
+        rating: Rating,
+        trust_level: TrustLevel,
+        paths: &[&str],
+    ) -> TrustedReview {
+        let commit = proof::CommitInfo {
+            id: "1".repeat(40),
+            tree: "2".repeat(40),
+            parents: vec![],
+            parent_tree: None,
+            patch_id: None,
+            summary: "Change things".into(),
+        };
+        let mut review = testing::trusted_review(from, commit, rating, trust_level);
+        review.review.paths = paths.iter().map(|path| path.to_string()).collect();
+        review
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn min_reviews() {
+        let policy = Policy {
+            min_reviews: 2,
+            ..Policy::default()
+        };
+        let (alice, bob) = (testing::id(), testing::id());
+        let changed = paths(&["src/main.rs"]);
+
+        let one = vec![review(&alice, Rating::Positive, TrustLevel::Medium, &[])];
+        assert_eq!(policy.evaluate(&one, &changed).unwrap(), Status::Unreviewed);
+
+        // Several reviews by the same Id count once.
+        let mut same_author = one.clone();
+        same_author.push(review(&alice, Rating::Strong, TrustLevel::Medium, &[]));
+        assert_eq!(
+            policy.evaluate(&same_author, &changed).unwrap(),
+            Status::Unreviewed
+        );
+
+        let mut two = one;
+        two.push(review(&bob, Rating::Positive, TrustLevel::Low, &[]));
+        assert_eq!(policy.evaluate(&two, &changed).unwrap(), Status::Verified);
+    }
+
+    #[test]
+    fn min_trust_level() {
+        let policy = Policy {
+            min_trust_level: TrustLevel::High,
+            ..Policy::default()
+        };
+        let alice = testing::id();
+        let changed = paths(&["src/main.rs"]);
+
+        let medium = vec![review(&alice, Rating::Strong, TrustLevel::Medium, &[])];
+        assert_eq!(
+            policy.evaluate(&medium, &changed).unwrap(),
+            Status::Unreviewed
+        );
+        let neutral = vec![review(&alice, Rating::Neutral, TrustLevel::High, &[])];
+        assert_eq!(
+            policy.evaluate(&neutral, &changed).unwrap(),
+            Status::Unreviewed
+        );
+        let high = vec![review(&alice, Rating::Positive, TrustLevel::High, &[])];
+        assert_eq!(policy.evaluate(&high, &changed).unwrap(), Status::Verified);
+    }
+
+    #[test]
+    fn path_rule() {
+        let policy = Policy {
+            paths: vec![PathRule {
+                glob: "src/crypto/**".into(),
+                min_reviews: Some(2),
+                min_trust_level: Some(TrustLevel::High),
+            }],
+            ..Policy::default()
+        };
+        let (alice, bob) = (testing::id(), testing::id());
+        let one = vec![review(&alice, Rating::Positive, TrustLevel::High, &[])];
+
+        assert_eq!(
+            policy.evaluate(&one, &paths(&["src/main.rs"])).unwrap(),
+            Status::Verified
+        );
+        let crypto = paths(&["src/main.rs", "src/crypto/aes.rs"]);
+        assert_eq!(policy.evaluate(&one, &crypto).unwrap(), Status::Unreviewed);
+
+        let mut low = one.clone();
+        low.push(review(&bob, Rating::Positive, TrustLevel::Low, &[]));
+        assert_eq!(policy.evaluate(&low, &crypto).unwrap(), Status::Unreviewed);
+
+        let mut high = one;
+        high.push(review(&bob, Rating::Positive, TrustLevel::High, &[]));
+        assert_eq!(policy.evaluate(&high, &crypto).unwrap(), Status::Verified);
+    }
+
+    #[test]
+    fn distrust_veto() {
+        let (alice, bob) = (testing::id(), testing::id());
+        let changed = paths(&["src/main.rs"]);
+        let reviews = vec![
+            review(&alice, Rating::Positive, TrustLevel::Medium, &[]),
+            review(&bob, Rating::Negative, TrustLevel::Low, &[]),
+        ];
+
+        let veto = Policy::default();
+        assert_eq!(
+            veto.evaluate(&reviews, &changed).unwrap(),
+            Status::Distrusted
+        );
+
+        let no_veto = Policy {
+            distrust_veto: false,
+            ..Policy::default()
+        };
+        assert_eq!(
+            no_veto.evaluate(&reviews, &changed).unwrap(),
+            Status::Verified
+        );
+        assert_eq!(
+            no_veto.evaluate(&reviews[1..], &changed).unwrap(),
+            Status::Distrusted
+        );
+
+        // Distrust from Ids that aren't trusted is ignored.
+        let untrusted = vec![
+            review(&alice, Rating::Positive, TrustLevel::Medium, &[]),
+            review(&bob, Rating::Dangerous, TrustLevel::None, &[]),
+        ];
+        assert_eq!(
+            veto.evaluate(&untrusted, &changed).unwrap(),
+            Status::Verified
+        );
+    }
+
+    #[test]
+    fn path_scoped_reviews_combine() {
+        let policy = Policy::default();
+        let (alice, bob) = (testing::id(), testing::id());
+        let changed = paths(&["src/lib.rs", "docs/guide.md"]);
+        let src = review(&alice, Rating::Positive, TrustLevel::Medium, &["src"]);
+        let docs = review(&bob, Rating::Positive, TrustLevel::Medium, &["docs/*.md"]);
+
+        assert_eq!(
+            policy.evaluate(&[src.clone()], &changed).unwrap(),
+            Status::Unreviewed
+        );
+        assert_eq!(
+            policy.evaluate(&[src, docs], &changed).unwrap(),
+            Status::Verified
+        );
+    }
+
+    #[test]
+    fn invalid_path_pattern_covers_nothing() {
+        let policy = Policy::default();
+        let (alice, mallory) = (testing::id(), testing::id());
+        let changed = paths(&["src/lib.rs"]);
+        let invalid = vec![
+            review(&alice, Rating::Positive, TrustLevel::Medium, &[]),
+            review(&mallory, Rating::Positive, TrustLevel::None, &["["]),
+        ];
+        assert_eq!(
+            policy.evaluate(&invalid, &changed).unwrap(),
+            Status::Verified
+        );
+
+        let trusted_invalid = vec![review(&alice, Rating::Positive, TrustLevel::Medium, &["["])];
+        assert_eq!(
+            policy.evaluate(&trusted_invalid, &changed).unwrap(),
+            Status::Unreviewed
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Review proofs shared through the repository's own remotes.
+//!
+//! A remote can publish proofs as notes (see [`notes`](../notes/index.html))
+//! or as proof repositories pushed to the `refs/crev/*` namespace, e.g. with
+//! `git push origin <proof branch>:refs/crev/<name>`. Fetched data is kept
+//! under `refs/crev-remotes/<remote>/`, apart from the local notes.
+use crev_data::proof::Proof;
+
+use crate::notes;
+use crate::prelude::*;
+
+/// Namespace of proof repositories published in a remote.
+const PROOFS_NAMESPACE: &str = "refs/crev/";
+
+/// Local namespace holding the data fetched from remotes.
+const FETCHED_NAMESPACE: &str = "refs/crev-remotes/";
+
+/// Credentials from the SSH agent or Git's credential helpers.
+///
+/// libgit2 asks again after a credential is rejected, so each kind of
+/// credential is offered only once; after that, authentication fails.
+fn remote_callbacks(config: &git2::Config) -> git2::RemoteCallbacks {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    let (mut tried_ssh_key, mut tried_helper, mut tried_default) = (false, false, false);
+    callbacks.credentials(move |url, username, allowed| {
+        if allowed.contains(git2::CredentialType::SSH_KEY) && !tried_ssh_key {
+            tried_ssh_key = true;
+            git2::Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) && !tried_helper {
+            tried_helper = true;
+            git2::Cred::credential_helper(config, url, username)
+        } else if !tried_default {
+            tried_default = true;
+            git2::Cred::default()
+        } else {
+            Err(git2::Error::from_str(&format!(
+                "Authentication failed for {}",
+                url
+            )))
+        }
+    });
+    callbacks
+}
+
+/// Fetch the review notes and proof repositories published in a remote.
+///
+/// Returns the number of refs fetched.
+pub fn fetch(repository: &git2::Repository, name: &str) -> Result<usize> {
+    let config = repository.config()?;
+    let notes_ref = notes::notes_ref(repository);
+    let mut remote = repository.find_remote(name)?;
+
+    // Fetching a ref missing from the remote is an error, so check first.
+    let advertised: Vec<String> = {
+        let connection = remote.connect_auth(
+            git2::Direction::Fetch,
+            Some(remote_callbacks(&config)),
+            None,
+        )?;
+        connection
+            .list()?
+            .iter()
+            .map(|head| head.name().to_string())
+            .collect()
+    };
+
+    let mut refspecs = vec![];
+    if advertised.contains(&notes_ref) {
+        refspecs.push(format!(
+            "+{}:{}{}/notes",
+            notes_ref, FETCHED_NAMESPACE, name
+        ));
+    }
+    if advertised
+        .iter()
+        .any(|reference| reference.starts_with(PROOFS_NAMESPACE))
+    {
+        refspecs.push(format!(
+            "+{}*:{}{}/proofs/*",
+            PROOFS_NAMESPACE, FETCHED_NAMESPACE, name
+        ));
+    }
+    if refspecs.is_empty() {
+        return Ok(0);
+    }
+
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(&config));
+    remote.fetch(&refspecs, Some(&mut fetch_options), None)?;
+    Ok(advertised
+        .iter()
+        .filter(|reference| **reference == notes_ref || reference.starts_with(PROOFS_NAMESPACE))
+        .count())
+}
+
+/// All proofs fetched from remotes.
+pub fn proofs(repository: &git2::Repository) -> Result<Vec<Proof>> {
+    let mut proofs = vec![];
+    for reference in repository.references_glob(&format!("{}*", FETCHED_NAMESPACE))? {
+        let reference = reference?;
+        let name = match reference.name() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        // `refs/crev-remotes/<remote>/notes`
+        let is_notes = name[FETCHED_NAMESPACE.len()..]
+            .split_once('/')
+            .map_or(false, |(_, rest)| rest == "notes");
+        if is_notes {
+            proofs.extend(notes::proofs_in(repository, &name)?);
+            continue;
+        }
+
+        // A proof repository: every `*.crev` file holds proofs.
+        let tree = reference.peel_to_tree()?;
+        let mut blob_ids = vec![];
+        tree.walk(git2::TreeWalkMode::PreOrder, |_, entry| {
+            let is_proof_file = entry.name().map_or(false, |n| n.ends_with(".crev"));
+            if is_proof_file && entry.kind() == Some(git2::ObjectType::Blob) {
+                blob_ids.push(entry.id());
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+        for blob_id in blob_ids {
+            let blob = repository.find_blob(blob_id)?;
+            match Proof::parse_from(blob.content()) {
+                Ok(parsed) => proofs.extend(parsed),
+                Err(e) => eprintln!("Ignoring unparsable proofs in {}: {}", name, e),
+            }
+        }
+    }
+    Ok(proofs)
+}
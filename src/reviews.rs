@@ -0,0 +1,196 @@
+//! Lookup of commit reviews in the local proof database.
+//!
+use std::collections::{HashMap, HashSet};
+
+use crev_data::TrustLevel;
+use crev_lib as crev;
+
+use crate::notes;
+use crate::prelude::*;
+use crate::proof;
+use crate::remote;
+
+/// A commit review together with the effective trust level of its author.
+#[derive(Debug, Clone)]
+pub struct TrustedReview {
+    pub review: proof::CommitReview,
+    pub trust_level: TrustLevel,
+    /// Trust distance of the author from the current Id, if trusted at all.
+    pub distance: Option<u64>,
+}
+
+/// Review state of a single commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Verified,
+    Unreviewed,
+    Distrusted,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let status = match self {
+            Status::Verified => "verified",
+            Status::Unreviewed => "unreviewed",
+            Status::Distrusted => "distrusted",
+        };
+        f.pad(status)
+    }
+}
+
+/// Overview of the trusted reviews of a commit.
+#[derive(Debug, Clone, Default)]
+pub struct ReviewSummary {
+    /// Number of reviews by trusted Ids.
+    pub trusted: usize,
+    /// Highest rating given by a trusted Id.
+    pub best_rating: Option<crev_data::Rating>,
+    /// Whether any trusted Id distrusts the commit.
+    pub distrusted: bool,
+}
+
+/// All known commit reviews, indexed by commit ID.
+#[derive(Debug, Default)]
+pub struct ReviewDb {
+    by_commit_id: HashMap<String, Vec<TrustedReview>>,
+    /// Reviews by the patch ID their author claims for the reviewed commit.
+    by_patch_id: HashMap<String, Vec<TrustedReview>>,
+    /// Reviews by the tree their author claims for the reviewed commit.
+    by_tree: HashMap<String, Vec<TrustedReview>>,
+    /// Reviews of whole trees.
+    snapshots: Vec<TrustedReview>,
+    /// Declarations of accepted history.
+    baselines: Vec<TrustedReview>,
+}
+
+impl ReviewDb {
+    /// Load commit reviews from all locally known proof repositories, from the
+    /// notes of the repository and from data fetched from its remotes, and
+    /// rate their authors through the trust graph of the current Id.
+    pub fn load(repository: &git2::Repository, params: &crev::TrustDistanceParams) -> Result<Self> {
+        let local = crev::Local::auto_open()?;
+        let own_id = local.get_current_userid()?;
+        let trust_set = local.load_db()?.calculate_trust_set(&own_id, params);
+
+        // The same proof may be both in a proof repository and in notes.
+        let mut seen = HashSet::new();
+        let mut db = Self::default();
+        let proofs = local
+            .proofs_iter()?
+            .chain(notes::proofs(repository)?)
+            .chain(remote::proofs(repository)?);
+        for proof in proofs {
+            if !seen.insert(proof.signature.clone()) {
+                continue;
+            }
+            let review = match proof::CommitReview::from_proof(&proof) {
+                Ok(Some(review)) => review,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Ignoring invalid commit review: {}", e);
+                    continue;
+                }
+            };
+            let (trust_level, distance) = if review.common.from.id == own_id {
+                (TrustLevel::High, Some(0))
+            } else {
+                let author = &review.common.from.id;
+                (
+                    trust_set.get_effective_trust_level(author),
+                    trust_set
+                        .trusted
+                        .get(author)
+                        .map(|details| details.distance),
+                )
+            };
+            db.insert(TrustedReview {
+                review,
+                trust_level,
+                distance,
+            });
+        }
+        Ok(db)
+    }
+
+    /// Add a review to the database.
+    pub fn insert(&mut self, trusted: TrustedReview) {
+        let review = &trusted.review;
+        if review.is_snapshot() {
+            self.snapshots.push(trusted);
+            return;
+        }
+        if review.is_baseline() {
+            self.baselines.push(trusted);
+            return;
+        }
+        if let Some(patch_id) = &review.commit.patch_id {
+            self.by_patch_id
+                .entry(patch_id.clone())
+                .or_default()
+                .push(trusted.clone());
+        }
+        self.by_tree
+            .entry(review.commit.tree.clone())
+            .or_default()
+            .push(trusted.clone());
+        self.by_commit_id
+            .entry(review.commit.id.clone())
+            .or_default()
+            .push(trusted);
+    }
+
+    /// Reviews of the given commit, by any author.
+    pub fn reviews_of(&self, commit_id: &str) -> &[TrustedReview] {
+        self.by_commit_id
+            .get(commit_id)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Reviews claiming the reviewed commit has the given patch ID.
+    ///
+    /// Only the claim of each review's own author counts: other reviews of the
+    /// same commit are not included, so an untrusted proof can't attach the
+    /// reviews of others to an arbitrary patch.
+    pub fn reviews_of_patch(&self, patch_id: &str) -> &[TrustedReview] {
+        self.by_patch_id
+            .get(patch_id)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Reviews claiming the reviewed commit has the given tree, like
+    /// [`reviews_of_patch`](#method.reviews_of_patch).
+    pub fn reviews_of_tree(&self, tree_id: &str) -> &[TrustedReview] {
+        self.by_tree.get(tree_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// All known snapshot reviews.
+    pub fn snapshots(&self) -> &[TrustedReview] {
+        &self.snapshots
+    }
+
+    /// All known baseline declarations.
+    pub fn baselines(&self) -> &[TrustedReview] {
+        &self.baselines
+    }
+
+    /// Overview of the reviews of a commit by trusted Ids.
+    pub fn summary(&self, commit_id: &str) -> ReviewSummary {
+        let mut summary = ReviewSummary::default();
+        for r in self.reviews_of(commit_id) {
+            if r.trust_level <= TrustLevel::None {
+                continue;
+            }
+            let rating = &r.review.review.rating;
+            summary.trusted += 1;
+            summary.distrusted |= proof::is_distrust(rating);
+            if summary.best_rating.as_ref().map_or(true, |best| {
+                proof::rating_rank(rating) > proof::rating_rank(best)
+            }) {
+                summary.best_rating = Some(rating.clone());
+            }
+        }
+        summary
+    }
+}
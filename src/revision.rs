@@ -0,0 +1,285 @@
+//! Resolution of revision arguments to commits, following `git rev-list`.
+//!
+use structopt::StructOpt;
+
+use crate::prelude::*;
+
+/// Options controlling which commits of a revision walk are selected.
+#[derive(Debug, StructOpt, Clone, Default)]
+pub struct WalkOptions {
+    /// Follow only the first parent of merge commits
+    #[structopt(long = "first-parent")]
+    pub first_parent: bool,
+
+    /// Skip merge commits
+    #[structopt(long = "no-merges")]
+    pub no_merges: bool,
+
+    /// List the oldest commits first
+    #[structopt(long = "reverse")]
+    pub reverse: bool,
+}
+
+/// Revision arguments, as accepted by most commands.
+#[derive(Debug, StructOpt, Clone)]
+pub struct RevisionRange {
+    /// Git revisions and ranges (e.g. `HEAD`, `A..B`, `A...B`, `^A B`) [default: HEAD]
+    #[structopt(name = "revision range")]
+    pub revisions: Vec<String>,
+
+    #[structopt(flatten)]
+    pub walk_options: WalkOptions,
+}
+
+impl RevisionRange {
+    /// Commits selected by the revision arguments, or the `HEAD` commit if
+    /// there are none.
+    pub fn commits<'a>(&self, repository: &'a git2::Repository) -> Result<Vec<git2::Commit<'a>>> {
+        if self.revisions.is_empty() {
+            return commits(repository, &["HEAD".into()], &self.walk_options);
+        }
+        commits(repository, &self.revisions, &self.walk_options)
+    }
+}
+
+/// A single parsed revision argument.
+enum Revision<'s> {
+    /// `B`: the commit and its ancestors.
+    Include(&'s str),
+    /// `^A`: excludes the commit and its ancestors.
+    Exclude(&'s str),
+    /// `A..B`: same as `^A B`.
+    Range(&'s str, &'s str),
+    /// `A...B`: commits reachable from either side but not from both.
+    SymmetricDifference(&'s str, &'s str),
+}
+
+impl<'s> Revision<'s> {
+    fn parse(argument: &'s str) -> Self {
+        let or_head = |s: &'s str| if s.is_empty() { "HEAD" } else { s };
+        if let Some(excluded) = argument.strip_prefix('^') {
+            Revision::Exclude(excluded)
+        } else if let Some(i) = argument.find("...") {
+            Revision::SymmetricDifference(or_head(&argument[..i]), or_head(&argument[i + 3..]))
+        } else if let Some(i) = argument.find("..") {
+            Revision::Range(or_head(&argument[..i]), or_head(&argument[i + 2..]))
+        } else {
+            Revision::Include(argument)
+        }
+    }
+}
+
+fn resolve(repository: &git2::Repository, revision: &str) -> Result<git2::Oid> {
+    let object = repository
+        .revparse_single(revision)
+        .or(Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Could not parse given revision specification: {}", revision),
+        )))?;
+    Ok(object.peel_to_commit()?.id())
+}
+
+/// Resolve revision arguments to commits, newest first unless `--reverse` is given.
+///
+/// Ranges and exclusions select commits like `git rev-list` does, which works
+/// across merges. If only plain revisions are given, just those commits are
+/// returned rather than their whole history, so that e.g. `HEAD` selects a
+/// single commit.
+pub fn commits<'a>(
+    repository: &'a git2::Repository,
+    revisions: &[String],
+    walk_options: &WalkOptions,
+) -> Result<Vec<git2::Commit<'a>>> {
+    let only_plain = revisions
+        .iter()
+        .all(|r| matches!(Revision::parse(r), Revision::Include(_)));
+    if !only_plain {
+        return walk(repository, revisions, walk_options);
+    }
+
+    let mut commits = vec![];
+    for revision in revisions {
+        commits.push(repository.find_commit(resolve(repository, revision)?)?);
+    }
+    Ok(filter(commits, walk_options))
+}
+
+/// Resolve revision arguments to commits exactly like `git rev-list`, newest
+/// first unless `--reverse` is given.
+///
+/// Unlike [`commits`], plain revisions select their whole history.
+pub fn walk<'a>(
+    repository: &'a git2::Repository,
+    revisions: &[String],
+    walk_options: &WalkOptions,
+) -> Result<Vec<git2::Commit<'a>>> {
+    let mut revwalk = repository.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    if walk_options.first_parent {
+        revwalk.simplify_first_parent()?;
+    }
+    for revision in revisions {
+        match Revision::parse(revision) {
+            Revision::Include(revision) => revwalk.push(resolve(repository, revision)?)?,
+            Revision::Exclude(revision) => revwalk.hide(resolve(repository, revision)?)?,
+            Revision::Range(from, to) => {
+                revwalk.hide(resolve(repository, from)?)?;
+                revwalk.push(resolve(repository, to)?)?;
+            }
+            Revision::SymmetricDifference(left, right) => {
+                let left = resolve(repository, left)?;
+                let right = resolve(repository, right)?;
+                revwalk.push(left)?;
+                revwalk.push(right)?;
+                // Unrelated histories have no merge base.
+                if let Ok(merge_bases) = repository.merge_bases(left, right) {
+                    for merge_base in merge_bases.iter() {
+                        revwalk.hide(*merge_base)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut commits = vec![];
+    for commit_id in revwalk {
+        commits.push(repository.find_commit(commit_id?)?);
+    }
+    Ok(filter(commits, walk_options))
+}
+
+fn filter<'a>(
+    mut commits: Vec<git2::Commit<'a>>,
+    walk_options: &WalkOptions,
+) -> Vec<git2::Commit<'a>> {
+    if walk_options.no_merges {
+        commits.retain(|commit| commit.parent_count() <= 1);
+    }
+    if walk_options.reverse {
+        commits.reverse();
+    }
+    commits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRepo;
+    use std::collections::BTreeSet;
+
+    /// Commits of a history with a merged feature branch:
+    ///
+    /// ```text
+    /// root - m1 - m2 ----- merge - m3
+    ///          \           /
+    ///           f1 ----- f2
+    /// ```
+    struct History {
+        repo: TestRepo,
+        root: git2::Oid,
+        m1: git2::Oid,
+        m2: git2::Oid,
+        f1: git2::Oid,
+        f2: git2::Oid,
+        merge: git2::Oid,
+        m3: git2::Oid,
+    }
+
+    impl History {
+        fn new() -> Self {
+            let repo = TestRepo::new();
+            let root = repo.commit(&[], &[("README", "root\n")], "root");
+            let m1 = repo.commit(&[root], &[("main.txt", "1\n")], "m1");
+            let f1 = repo.commit(&[m1], &[("feature.txt", "1\n")], "f1");
+            let f2 = repo.commit(&[f1], &[("feature.txt", "2\n")], "f2");
+            let m2 = repo.commit(&[m1], &[("main.txt", "2\n")], "m2");
+            let merge = repo.commit(&[m2, f2], &[("feature.txt", "2\n")], "merge");
+            let m3 = repo.commit(&[merge], &[("main.txt", "3\n")], "m3");
+            Self {
+                repo,
+                root,
+                m1,
+                m2,
+                f1,
+                f2,
+                merge,
+                m3,
+            }
+        }
+
+        fn select(&self, revisions: &[String], walk_options: &WalkOptions) -> BTreeSet<git2::Oid> {
+            commits(&self.repo.repository, revisions, walk_options)
+                .unwrap()
+                .iter()
+                .map(git2::Commit::id)
+                .collect()
+        }
+    }
+
+    fn set(ids: &[git2::Oid]) -> BTreeSet<git2::Oid> {
+        ids.iter().cloned().collect()
+    }
+
+    #[test]
+    fn plain_revision_selects_single_commit() {
+        let h = History::new();
+        let selected = h.select(&[h.m3.to_string()], &WalkOptions::default());
+        assert_eq!(selected, set(&[h.m3]));
+    }
+
+    #[test]
+    fn range_from_off_first_parent_line() {
+        let h = History::new();
+        let selected = h.select(&[format!("{}..{}", h.f2, h.m3)], &WalkOptions::default());
+        assert_eq!(selected, set(&[h.m3, h.merge, h.m2]));
+    }
+
+    #[test]
+    fn symmetric_difference() {
+        let h = History::new();
+        let selected = h.select(&[format!("{}...{}", h.m2, h.f2)], &WalkOptions::default());
+        assert_eq!(selected, set(&[h.m2, h.f1, h.f2]));
+    }
+
+    #[test]
+    fn exclusion() {
+        let h = History::new();
+        let selected = h.select(
+            &[format!("^{}", h.m1), h.m3.to_string()],
+            &WalkOptions::default(),
+        );
+        assert_eq!(selected, set(&[h.m3, h.merge, h.m2, h.f2, h.f1]));
+    }
+
+    #[test]
+    fn multiple_ranges() {
+        let h = History::new();
+        let selected = h.select(
+            &[format!("{}..{}", h.m1, h.m2), format!("{}..{}", h.f1, h.f2)],
+            &WalkOptions::default(),
+        );
+        assert_eq!(selected, set(&[h.m2, h.f2]));
+    }
+
+    #[test]
+    fn first_parent() {
+        let h = History::new();
+        let walk_options = WalkOptions {
+            first_parent: true,
+            ..WalkOptions::default()
+        };
+        let selected = h.select(&[format!("{}..{}", h.root, h.m3)], &walk_options);
+        assert_eq!(selected, set(&[h.m3, h.merge, h.m2, h.m1]));
+    }
+
+    #[test]
+    fn no_merges() {
+        let h = History::new();
+        let walk_options = WalkOptions {
+            no_merges: true,
+            ..WalkOptions::default()
+        };
+        let selected = h.select(&[format!("{}..{}", h.m1, h.m3)], &walk_options);
+        assert_eq!(selected, set(&[h.m3, h.m2, h.f2, h.f1]));
+    }
+}
@@ -9,6 +9,7 @@ use crev_lib::{self, local::Local, ProofStore};
 use failure::format_err;
 use serde::Deserialize;
 
+use crate::diff;
 use crate::term;
 
 pub fn load_stdin_with_prompt() -> Result<Vec<u8>> {
@@ -21,6 +22,16 @@ pub fn load_stdin_with_prompt() -> Result<Vec<u8>> {
     Ok(s)
 }
 
+/// Parse a review level: `none`, `low`, `medium` or `high`.
+pub fn parse_level(s: &str) -> std::result::Result<crev_data::Level, String> {
+    serde_yaml::from_str(s).map_err(|_| format!("Invalid level: {}", s))
+}
+
+/// Parse a review rating: `dangerous`, `negative`, `neutral`, `positive` or `strong`.
+pub fn parse_rating(s: &str) -> std::result::Result<crev_data::Rating, String> {
+    serde_yaml::from_str(s).map_err(|_| format!("Invalid rating: {}", s))
+}
+
 /// Data from `.cargo_vcs_info.json`
 #[derive(Debug, Clone, Deserialize)]
 pub struct VcsInfoJson {
@@ -101,3 +112,47 @@ pub fn maybe_store(
 
     Ok(())
 }
+
+/// Paths changed by a commit, relative to its first parent.
+pub fn changed_paths(
+    repository: &git2::Repository,
+    commit: &git2::Commit,
+) -> Result<Vec<std::path::PathBuf>> {
+    let diff = diff::commit_diff(repository, commit, &diff::DiffOptions::exact())?;
+    Ok(diff
+        .deltas()
+        .flat_map(|delta| vec![delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .map(std::path::Path::to_path_buf)
+        .collect())
+}
+
+/// Patch ID of a commit, as computed by `git patch-id --stable`.
+///
+/// Commits with the same patch ID make the same change, e.g. after a rebase or
+/// cherry-pick. Returns `None` for commits that don't change anything.
+pub fn patch_id(
+    repository: &git2::Repository,
+    commit: &git2::Commit,
+) -> Result<Option<git2::Oid>> {
+    let diff = diff::commit_diff(repository, commit, &diff::DiffOptions::exact())?;
+    if diff.deltas().len() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(diff.patchid(None)?))
+}
+
+/// Whether a path matches a pathspec, given as a path prefix or a glob.
+pub fn path_matches(pathspec: &str, path: &std::path::Path) -> Result<bool> {
+    let pattern = glob::Pattern::new(pathspec)
+        .with_context(|_| format_err!("Invalid path pattern: {}", pathspec))?;
+    Ok(path.starts_with(pathspec) || pattern.matches_path(path))
+}
+
+/// Format a Git timestamp like `git log` does, in the time zone it was recorded in.
+pub fn format_git_time(time: &git2::Time) -> String {
+    let offset = time::UtcOffset::minutes(time.offset_minutes() as i16);
+    time::OffsetDateTime::from_unix_timestamp(time.seconds())
+        .to_offset(offset)
+        .format("%a %b %-d %T %Y %z")
+}
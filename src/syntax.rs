@@ -0,0 +1,375 @@
+//! Syntax highlighting of diff lines.
+//!
+//! Keywords, strings, comments and numbers are colored for a few common
+//! languages, recognized by file extension. Lines are highlighted one at a
+//! time, so constructs spanning several lines, like block comments or
+//! multi-line strings, are only colored on the line they start on.
+use std::path::Path;
+
+const RESET: &str = "\x1b[m";
+const KEYWORD: &str = "\x1b[35m";
+const STRING: &str = "\x1b[33m";
+const COMMENT: &str = "\x1b[90m";
+const NUMBER: &str = "\x1b[36m";
+
+/// Lexical rules of a language, as far as needed for highlighting.
+pub struct Language {
+    extensions: &'static [&'static str],
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+}
+
+const C_BLOCK_COMMENT: Option<(&str, &str)> = Some(("/*", "*/"));
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        extensions: &["rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where", "while",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK_COMMENT,
+        quotes: &['"'],
+    },
+    Language {
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hh", "hpp"],
+        keywords: &[
+            "auto",
+            "bool",
+            "break",
+            "case",
+            "char",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "double",
+            "else",
+            "enum",
+            "extern",
+            "false",
+            "float",
+            "for",
+            "goto",
+            "if",
+            "inline",
+            "int",
+            "long",
+            "namespace",
+            "new",
+            "nullptr",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "short",
+            "signed",
+            "sizeof",
+            "static",
+            "struct",
+            "switch",
+            "template",
+            "this",
+            "true",
+            "typedef",
+            "typename",
+            "union",
+            "unsigned",
+            "virtual",
+            "void",
+            "volatile",
+            "while",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK_COMMENT,
+        quotes: &['"', '\''],
+    },
+    Language {
+        extensions: &["go"],
+        keywords: &[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "fallthrough",
+            "false",
+            "for",
+            "func",
+            "go",
+            "goto",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK_COMMENT,
+        quotes: &['"', '\'', '`'],
+    },
+    Language {
+        extensions: &["java", "kt", "scala"],
+        keywords: &[
+            "abstract",
+            "boolean",
+            "break",
+            "byte",
+            "case",
+            "catch",
+            "char",
+            "class",
+            "continue",
+            "default",
+            "do",
+            "double",
+            "else",
+            "enum",
+            "extends",
+            "false",
+            "final",
+            "finally",
+            "float",
+            "for",
+            "if",
+            "implements",
+            "import",
+            "instanceof",
+            "int",
+            "interface",
+            "long",
+            "new",
+            "null",
+            "package",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "short",
+            "static",
+            "super",
+            "switch",
+            "synchronized",
+            "this",
+            "throw",
+            "throws",
+            "true",
+            "try",
+            "void",
+            "volatile",
+            "while",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK_COMMENT,
+        quotes: &['"', '\''],
+    },
+    Language {
+        extensions: &["js", "jsx", "mjs", "ts", "tsx"],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "return",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+        line_comments: &["//"],
+        block_comment: C_BLOCK_COMMENT,
+        quotes: &['"', '\'', '`'],
+    },
+    Language {
+        extensions: &["py"],
+        keywords: &[
+            "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+            "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+            "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+            "return", "try", "while", "with", "yield",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+    },
+    Language {
+        extensions: &["sh", "bash", "zsh"],
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "local", "return", "then", "until", "while",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+    },
+];
+
+impl Language {
+    /// Language of a file, from its extension.
+    pub fn from_path(path: &Path) -> Option<&'static Language> {
+        let extension = path.extension()?.to_str()?;
+        LANGUAGES
+            .iter()
+            .find(|language| language.extensions.iter().any(|e| *e == extension))
+    }
+
+    /// Highlight a line with ANSI colors. `base` is the style of the whole
+    /// line, e.g. a background color, and is restored after each token.
+    pub fn highlight(&self, line: &str, base: &str) -> String {
+        let mut out = String::new();
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            let block_comment = self
+                .block_comment
+                .filter(|(start, _)| rest.starts_with(start));
+            let (style, len) = if self.line_comments.iter().any(|m| rest.starts_with(m)) {
+                (Some(COMMENT), rest.len())
+            } else if let Some((start, end)) = block_comment {
+                let len = rest[start.len()..]
+                    .find(end)
+                    .map_or(rest.len(), |i| start.len() + i + end.len());
+                (Some(COMMENT), len)
+            } else if self.quotes.contains(&c) {
+                (Some(STRING), string_len(rest, c))
+            } else if c.is_ascii_digit() {
+                (Some(NUMBER), word_len(rest))
+            } else if c.is_alphabetic() || c == '_' {
+                let len = word_len(rest);
+                let is_keyword = self.keywords.iter().any(|k| *k == &rest[..len]);
+                (Some(KEYWORD).filter(|_| is_keyword), len)
+            } else {
+                (None, c.len_utf8())
+            };
+
+            let (token, remainder) = rest.split_at(len);
+            match style {
+                Some(style) => out += &format!("{}{}{}{}", style, token, RESET, base),
+                None => out += token,
+            }
+            rest = remainder;
+        }
+        out
+    }
+}
+
+/// Length of the identifier or number at the start of `text`.
+fn word_len(text: &str) -> usize {
+    text.char_indices()
+        .find(|(_, c)| !c.is_alphanumeric() && *c != '_')
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Length of the string literal at the start of `text`, including its quotes.
+/// An unterminated string extends to the end of the line.
+fn string_len(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust() -> &'static Language {
+        Language::from_path(Path::new("src/main.rs")).unwrap()
+    }
+
+    #[test]
+    fn recognizes_languages_by_extension() {
+        assert!(Language::from_path(Path::new("setup.py")).is_some());
+        assert!(Language::from_path(Path::new("README")).is_none());
+        assert!(Language::from_path(Path::new("notes.txt")).is_none());
+    }
+
+    #[test]
+    fn highlights_tokens() {
+        assert_eq!(
+            rust().highlight("let x = 42;", ""),
+            format!("{}let{} x = {}42{};", KEYWORD, RESET, NUMBER, RESET)
+        );
+        assert_eq!(
+            rust().highlight("f(\"fn \\\"\") // fn", ""),
+            format!(
+                "f({}\"fn \\\"\"{}) {}// fn{}",
+                STRING, RESET, COMMENT, RESET
+            )
+        );
+        assert_eq!(
+            rust().highlight("a /* fn */ b", ""),
+            format!("a {}/* fn */{} b", COMMENT, RESET)
+        );
+    }
+
+    #[test]
+    fn restores_the_base_style() {
+        let base = "\x1b[48;5;22m";
+        assert_eq!(
+            rust().highlight("fn f", base),
+            format!("{}fn{}{} f", KEYWORD, RESET, base)
+        );
+    }
+
+    #[test]
+    fn keywords_must_be_whole_words() {
+        assert_eq!(rust().highlight("letter fn_name", ""), "letter fn_name");
+    }
+}
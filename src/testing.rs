@@ -0,0 +1,129 @@
+//! Fixtures for tests: scratch Git repositories and crev Ids.
+use std::path::Path;
+
+use crev_data::TrustLevel;
+
+use crate::index;
+use crate::proof;
+use crate::reviews::TrustedReview;
+
+/// A Git repository in a temporary directory, removed when dropped.
+pub struct TestRepo {
+    pub repository: git2::Repository,
+    _dir: tempfile::TempDir,
+}
+
+impl TestRepo {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let repository = git2::Repository::init(dir.path()).unwrap();
+        let mut config = repository.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        Self {
+            repository,
+            _dir: dir,
+        }
+    }
+
+    /// Create a commit with the given parents, whose tree is that of its first
+    /// parent with `files` (path and contents) added or replaced.
+    pub fn commit(
+        &self,
+        parents: &[git2::Oid],
+        files: &[(&str, &str)],
+        message: &str,
+    ) -> git2::Oid {
+        let workdir = self.repository.workdir().unwrap();
+        let mut index = self.repository.index().unwrap();
+        index.clear().unwrap();
+        if let Some(parent) = parents.first() {
+            let parent = self.repository.find_commit(*parent).unwrap();
+            index.read_tree(&parent.tree().unwrap()).unwrap();
+        }
+        for (path, contents) in files {
+            let file_path = workdir.join(path);
+            std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            std::fs::write(&file_path, contents).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        let tree = self
+            .repository
+            .find_tree(index.write_tree().unwrap())
+            .unwrap();
+        let parents: Vec<git2::Commit> = parents
+            .iter()
+            .map(|id| self.repository.find_commit(*id).unwrap())
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        self.repository
+            .commit(None, &signature, &signature, message, &tree, &parents)
+            .unwrap()
+    }
+
+    pub fn find_commit(&self, id: git2::Oid) -> git2::Commit {
+        self.repository.find_commit(id).unwrap()
+    }
+}
+
+/// A new crev Id.
+pub fn id() -> crev_data::id::UnlockedId {
+    crev_data::id::UnlockedId::generate_for_git_url("https://example.com/crev-proofs")
+}
+
+/// A review with the given rating and default levels.
+pub fn review(rating: crev_data::Rating) -> crev_data::Review {
+    crev_data::Review {
+        thoroughness: crev_data::Level::Medium,
+        understanding: crev_data::Level::Medium,
+        rating,
+    }
+}
+
+/// A review of a commit by `from`, whose author is trusted at `trust_level`.
+pub fn trusted_review(
+    from: &crev_data::id::UnlockedId,
+    commit: proof::CommitInfo,
+    rating: crev_data::Rating,
+    trust_level: TrustLevel,
+) -> TrustedReview {
+    TrustedReview {
+        review: proof::CommitReview {
+            common: crev_data::proof::Common {
+                kind: Some(proof::KIND.into()),
+                version: proof::CURRENT_VERSION,
+                date: crev_common::now(),
+                from: from.as_pubid().clone(),
+            },
+            repository: vec![],
+            commit,
+            review: review(rating),
+            comment: "".into(),
+            paths: vec![],
+        },
+        trust_level,
+        distance: Some(1),
+    }
+}
+
+/// A review of `commit` by `id`, signed after applying `edit` to its body.
+pub fn signed_review(
+    repo: &TestRepo,
+    commit: git2::Oid,
+    id: &crev_data::id::UnlockedId,
+    edit: impl FnOnce(&mut proof::CommitReview),
+) -> (proof::CommitReview, crev_data::proof::Proof) {
+    let commit = repo.find_commit(commit);
+    let entry = index::IndexEntry::new(
+        &repo.repository,
+        &commit,
+        review(crev_data::Rating::Positive),
+    )
+    .unwrap();
+    let mut review =
+        proof::CommitReview::new(id.as_pubid().clone(), &repo.repository, &commit, &entry).unwrap();
+    edit(&mut review);
+    let proof = review.sign_by(id).unwrap();
+    (review, proof)
+}
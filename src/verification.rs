@@ -0,0 +1,632 @@
+//! Evaluation of commits against known reviews and the repository policy.
+//!
+//! Besides its own reviews, a commit is credited with the reviews of commits
+//! making the same change, so that reviews survive rebases, cherry-picks and
+//! squash merges. Commits in the history of a reviewed snapshot, or of a
+//! baseline declared by trusted Ids, need no review of their own.
+use crev_lib as crev;
+
+use crate::policy;
+use crate::prelude::*;
+use crate::proof;
+use crate::reviews::{ReviewDb, Status, TrustedReview};
+use crate::shared;
+
+/// Longest squashed branch whose reviews are credited to the squash.
+const MAX_SQUASHED_COMMITS: usize = 1000;
+
+/// Why the reviews of another commit apply to a commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Equivalence {
+    /// Both commits have the same patch ID.
+    Patch,
+    /// The commit has the same net change as a reviewed commit, or a chain of
+    /// reviewed commits, on top of the same tree, as with a squash merge of a
+    /// reviewed branch.
+    Tree,
+    /// The commit is part of the history of a reviewed snapshot.
+    Snapshot,
+    /// The commit is part of the history of an accepted baseline.
+    Baseline,
+}
+
+impl std::fmt::Display for Equivalence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let equivalence = match self {
+            Equivalence::Patch => "equivalent patch",
+            Equivalence::Tree => "identical tree change",
+            Equivalence::Snapshot => "snapshot",
+            Equivalence::Baseline => "accepted baseline",
+        };
+        f.pad(equivalence)
+    }
+}
+
+/// Review state of a commit, and whether it relies on reviews of equivalent
+/// commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Evaluation {
+    pub status: Status,
+    /// Set when reviews of equivalent commits changed the status.
+    pub equivalence: Option<Equivalence>,
+}
+
+impl std::fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.equivalence {
+            Some(equivalence) if self.status == Status::Verified => {
+                write!(f, "reviewed via {}", equivalence)
+            }
+            Some(equivalence) => write!(f, "{} via {}", self.status, equivalence),
+            None => write!(f, "{}", self.status),
+        }
+    }
+}
+
+pub struct Verifier<'a> {
+    pub repository: &'a git2::Repository,
+    pub db: ReviewDb,
+    pub policy: policy::Policy,
+}
+
+impl<'a> Verifier<'a> {
+    /// Load the review database and the repository policy.
+    pub fn load(
+        repository: &'a git2::Repository,
+        params: &crev::TrustDistanceParams,
+    ) -> Result<Self> {
+        Ok(Self {
+            repository,
+            db: ReviewDb::load(repository, params)?,
+            policy: policy::Policy::load(repository)?,
+        })
+    }
+
+    /// Review state of a commit under the repository policy.
+    pub fn status(&self, commit: &git2::Commit) -> Result<Status> {
+        Ok(self.evaluate(commit)?.status)
+    }
+
+    /// Review state of a commit under the repository policy, taking reviews of
+    /// equivalent commits into account if its own reviews are not sufficient.
+    pub fn evaluate(&self, commit: &git2::Commit) -> Result<Evaluation> {
+        let changed_paths = shared::changed_paths(self.repository, commit)?;
+        let own_reviews = self.db.reviews_of(&commit.id().to_string());
+        let status = self.policy.evaluate(own_reviews, &changed_paths)?;
+        if status == Status::Verified {
+            return Ok(Evaluation {
+                status,
+                equivalence: None,
+            });
+        }
+
+        let equivalent = self.equivalent_reviews(commit)?;
+        let mut reviews: Vec<TrustedReview> = own_reviews.to_vec();
+        reviews.extend(equivalent.iter().map(|(review, _)| (*review).clone()));
+        let combined = self.policy.evaluate(&reviews, &changed_paths)?;
+
+        // Only sufficiently trusted snapshots or baselines make up for missing reviews.
+        if combined == Status::Unreviewed {
+            for (declarations, equivalence) in &[
+                (self.db.snapshots(), Equivalence::Snapshot),
+                (self.db.baselines(), Equivalence::Baseline),
+            ] {
+                let covering: Vec<TrustedReview> = self
+                    .covering(declarations, commit)
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                if !covering.is_empty()
+                    && self.policy.evaluate(&covering, &changed_paths)? == Status::Verified
+                {
+                    return Ok(Evaluation {
+                        status: Status::Verified,
+                        equivalence: Some(*equivalence),
+                    });
+                }
+            }
+        }
+        Ok(Evaluation {
+            status: combined,
+            equivalence: equivalent
+                .first()
+                .filter(|_| combined != status)
+                .map(|(_, equivalence)| *equivalence),
+        })
+    }
+
+    /// Reviews of other commits making the same change as a commit.
+    pub fn equivalent_reviews(
+        &self,
+        commit: &git2::Commit,
+    ) -> Result<Vec<(&TrustedReview, Equivalence)>> {
+        let commit_id = commit.id().to_string();
+        let mut equivalent = vec![];
+        if let Some(patch_id) = shared::patch_id(self.repository, commit)? {
+            for review in self.db.reviews_of_patch(&patch_id.to_string()) {
+                if review.review.commit.id != commit_id {
+                    equivalent.push((review, Equivalence::Patch));
+                }
+            }
+        }
+
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => parent.tree_id().to_string(),
+            None => return Ok(equivalent),
+        };
+        let mut squashed: Vec<&str> = vec![];
+        for review in self.db.reviews_of_tree(&commit.tree_id().to_string()) {
+            let tip = &review.review.commit;
+            let author = &review.review.common.from.id;
+            if tip.id == commit_id
+                || !proof::is_trust(&review.review.review.rating)
+                || equivalent.iter().any(|(other, _)| {
+                    other.review.commit.id == tip.id && other.review.common.from.id == *author
+                })
+            {
+                continue;
+            }
+            let chain = match self.reviewed_chain(review, &parent_tree) {
+                Some(chain) => chain,
+                None => continue,
+            };
+            equivalent.push((review, Equivalence::Tree));
+            // Distrust of any squashed commit applies to the squash as well.
+            for reviewed in chain {
+                if squashed.contains(&reviewed.id.as_str()) {
+                    continue;
+                }
+                squashed.push(&reviewed.id);
+                for review in self.db.reviews_of(&reviewed.id) {
+                    if proof::is_distrust(&review.review.review.rating) {
+                        equivalent.push((review, Equivalence::Tree));
+                    }
+                }
+            }
+        }
+        Ok(equivalent)
+    }
+
+    /// Commits leading from the commit of `tip` back to a commit whose first
+    /// parent has the tree `parent_tree`, following first parents: the commits
+    /// of a branch squashed into a single commit, newest first.
+    ///
+    /// Only the proofs of the author of `tip` are followed, so the author
+    /// vouches for the whole branch: returns `None` if they didn't give a
+    /// commit on the way a trusting review of all paths, or if a proof
+    /// contradicts a commit in the repository.
+    fn reviewed_chain<'d>(
+        &'d self,
+        tip: &'d TrustedReview,
+        parent_tree: &str,
+    ) -> Option<Vec<&'d proof::CommitInfo>> {
+        let author = &tip.review.common.from.id;
+        let mut current = &tip.review.commit;
+        let mut chain = vec![current];
+        loop {
+            if !self.matches_repository(current) {
+                return None;
+            }
+            if self.parent_tree_of(current).as_deref() == Some(parent_tree) {
+                return Some(chain);
+            }
+            // Proofs can claim any parents, so don't trust them to end.
+            if chain.len() >= MAX_SQUASHED_COMMITS {
+                return None;
+            }
+            let parent_id = current.parents.first()?;
+            let parent_review = self.db.reviews_of(parent_id).iter().find(|review| {
+                review.review.common.from.id == *author
+                    && proof::is_trust(&review.review.review.rating)
+                    && review.review.paths.is_empty()
+            })?;
+            current = &parent_review.review.commit;
+            chain.push(current);
+        }
+    }
+
+    /// Whether the tree and parents a proof records for a commit match the
+    /// commit in the repository. Commits missing locally can't be checked.
+    fn matches_repository(&self, reviewed: &proof::CommitInfo) -> bool {
+        let commit = match git2::Oid::from_str(&reviewed.id)
+            .and_then(|id| self.repository.find_commit(id))
+        {
+            Ok(commit) => commit,
+            Err(_) => return true,
+        };
+        let parents: Vec<String> = commit.parent_ids().map(|id| id.to_string()).collect();
+        let parent_tree = commit
+            .parents()
+            .next()
+            .map(|parent| parent.tree_id().to_string());
+        commit.tree_id().to_string() == reviewed.tree
+            && parents == reviewed.parents
+            && (reviewed.parent_tree.is_none() || reviewed.parent_tree == parent_tree)
+    }
+
+    /// Snapshots or baselines covering a commit: those of its own tree, and
+    /// those of a commit it is an ancestor of.
+    fn covering<'r>(
+        &self,
+        declarations: &'r [TrustedReview],
+        commit: &git2::Commit,
+    ) -> Vec<&'r TrustedReview> {
+        let tree_id = commit.tree_id().to_string();
+        let mut covering = vec![];
+        for review in declarations {
+            let snapshot = &review.review.commit;
+            let covers = snapshot.tree == tree_id
+                || match git2::Oid::from_str(&snapshot.id) {
+                    Ok(id) if id == commit.id() => true,
+                    // Snapshots of commits missing locally can't be related by history.
+                    Ok(id) => self
+                        .repository
+                        .graph_descendant_of(id, commit.id())
+                        .unwrap_or(false),
+                    Err(_) => false,
+                };
+            if covers {
+                covering.push(review);
+            }
+        }
+        covering
+    }
+
+    /// Tree of the first parent of a reviewed commit, from the repository or,
+    /// for parents missing locally, from its proof.
+    fn parent_tree_of(&self, reviewed: &proof::CommitInfo) -> Option<String> {
+        let parent_id = reviewed.parents.first()?;
+        match git2::Oid::from_str(parent_id).and_then(|id| self.repository.find_commit(id)) {
+            Ok(parent) => Some(parent.tree_id().to_string()),
+            Err(_) => reviewed.parent_tree.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TestRepo};
+    use crev_data::{Rating, TrustLevel};
+
+    /// A two-commit feature branch, and the commit made by `git merge --squash`
+    /// of it onto an unchanged main branch.
+    struct SquashedBranch {
+        repo: TestRepo,
+        base: git2::Oid,
+        f1: git2::Oid,
+        f2: git2::Oid,
+        squash: git2::Oid,
+    }
+
+    /// A review of a commit of `repo` by `from`, trusted at a medium level.
+    fn review(
+        repo: &TestRepo,
+        from: &crev_data::id::UnlockedId,
+        commit_id: git2::Oid,
+        rating: Rating,
+    ) -> TrustedReview {
+        let commit = repo.find_commit(commit_id);
+        let info = proof::CommitInfo::from_commit(&repo.repository, &commit).unwrap();
+        testing::trusted_review(from, info, rating, TrustLevel::Medium)
+    }
+
+    /// Evaluate a commit of `repo` under the default policy, given all known reviews.
+    fn evaluate(repo: &TestRepo, commit_id: git2::Oid, reviews: Vec<TrustedReview>) -> Evaluation {
+        evaluate_under(policy::Policy::default(), repo, commit_id, reviews)
+    }
+
+    fn evaluate_under(
+        policy: policy::Policy,
+        repo: &TestRepo,
+        commit_id: git2::Oid,
+        reviews: Vec<TrustedReview>,
+    ) -> Evaluation {
+        let mut db = ReviewDb::default();
+        for review in reviews {
+            db.insert(review);
+        }
+        let verifier = Verifier {
+            repository: &repo.repository,
+            db,
+            policy,
+        };
+        verifier.evaluate(&repo.find_commit(commit_id)).unwrap()
+    }
+
+    impl SquashedBranch {
+        fn new() -> Self {
+            let repo = TestRepo::new();
+            let base = repo.commit(&[], &[("README", "base\n")], "base");
+            let f1 = repo.commit(&[base], &[("a.txt", "a\n")], "Add a");
+            let f2 = repo.commit(&[f1], &[("b.txt", "b\n")], "Add b");
+            let squash = repo.commit(&[base], &[("a.txt", "a\n"), ("b.txt", "b\n")], "Feature");
+            assert_eq!(
+                repo.find_commit(squash).tree_id(),
+                repo.find_commit(f2).tree_id()
+            );
+            Self {
+                repo,
+                base,
+                f1,
+                f2,
+                squash,
+            }
+        }
+
+        fn review(
+            &self,
+            from: &crev_data::id::UnlockedId,
+            commit_id: git2::Oid,
+            rating: Rating,
+        ) -> TrustedReview {
+            review(&self.repo, from, commit_id, rating)
+        }
+
+        fn evaluate(&self, reviews: Vec<TrustedReview>) -> Evaluation {
+            evaluate(&self.repo, self.squash, reviews)
+        }
+    }
+
+    #[test]
+    fn squash_of_reviewed_branch_is_verified() {
+        let branch = SquashedBranch::new();
+        let alice = testing::id();
+        let evaluation = branch.evaluate(vec![
+            branch.review(&alice, branch.f1, Rating::Positive),
+            branch.review(&alice, branch.f2, Rating::Positive),
+        ]);
+        assert_eq!(
+            evaluation,
+            Evaluation {
+                status: Status::Verified,
+                equivalence: Some(Equivalence::Tree),
+            }
+        );
+    }
+
+    #[test]
+    fn squash_of_partly_reviewed_branch_is_unreviewed() {
+        let branch = SquashedBranch::new();
+        let (alice, bob) = (testing::id(), testing::id());
+
+        let only_last = branch.evaluate(vec![branch.review(&alice, branch.f2, Rating::Positive)]);
+        assert_eq!(only_last.status, Status::Unreviewed);
+
+        // Each commit is reviewed, but nobody vouches for the whole branch.
+        let split = branch.evaluate(vec![
+            branch.review(&alice, branch.f1, Rating::Positive),
+            branch.review(&bob, branch.f2, Rating::Positive),
+        ]);
+        assert_eq!(split.status, Status::Unreviewed);
+    }
+
+    #[test]
+    fn distrust_of_squashed_commit_applies() {
+        let branch = SquashedBranch::new();
+        let (alice, bob) = (testing::id(), testing::id());
+        let evaluation = branch.evaluate(vec![
+            branch.review(&alice, branch.f1, Rating::Positive),
+            branch.review(&bob, branch.f1, Rating::Negative),
+            branch.review(&alice, branch.f2, Rating::Positive),
+        ]);
+        assert_eq!(evaluation.status, Status::Distrusted);
+    }
+
+    #[test]
+    fn forged_tree_claim_is_not_credited() {
+        let branch = SquashedBranch::new();
+        let malicious = branch
+            .repo
+            .commit(&[branch.base], &[("a.txt", "evil\n")], "Feature");
+        let malicious_tree = branch.repo.find_commit(malicious).tree_id().to_string();
+        let (alice, mallory, bob) = (testing::id(), testing::id(), testing::id());
+        let reviews_claiming_malicious_tree = |from: &crev_data::id::UnlockedId| {
+            let mut reviews = vec![
+                branch.review(from, branch.f1, Rating::Positive),
+                branch.review(from, branch.f2, Rating::Positive),
+            ];
+            reviews[1].review.commit.tree = malicious_tree.clone();
+            reviews
+        };
+
+        // Mallory, who nobody trusts, claims the last commit of Alice's
+        // reviewed branch has the tree of the malicious commit.
+        let mut reviews = vec![
+            branch.review(&alice, branch.f1, Rating::Positive),
+            branch.review(&alice, branch.f2, Rating::Positive),
+        ];
+        for mut review in reviews_claiming_malicious_tree(&mallory) {
+            review.trust_level = TrustLevel::None;
+            review.distance = None;
+            reviews.push(review);
+        }
+        let evaluation = evaluate(&branch.repo, malicious, reviews);
+        assert_eq!(evaluation.status, Status::Unreviewed);
+
+        // Even a trusted Id can't claim a tree the local commit doesn't have.
+        let evaluation = evaluate(
+            &branch.repo,
+            malicious,
+            reviews_claiming_malicious_tree(&bob),
+        );
+        assert_eq!(evaluation.status, Status::Unreviewed);
+    }
+
+    #[test]
+    fn rebased_copy_of_reviewed_commit_is_verified() {
+        let repo = TestRepo::new();
+        let base = repo.commit(&[], &[("README", "base\n")], "base");
+        let reviewed = repo.commit(&[base], &[("a.txt", "a\n")], "Add a");
+        let other = repo.commit(&[base], &[("b.txt", "b\n")], "Add b");
+        let rebased = repo.commit(&[other], &[("a.txt", "a\n")], "Add a");
+
+        let alice = testing::id();
+        let evaluation = evaluate(
+            &repo,
+            rebased,
+            vec![review(&repo, &alice, reviewed, Rating::Positive)],
+        );
+        assert_eq!(
+            evaluation,
+            Evaluation {
+                status: Status::Verified,
+                equivalence: Some(Equivalence::Patch),
+            }
+        );
+    }
+
+    #[test]
+    fn claimed_patch_id_credits_only_its_author() {
+        let repo = TestRepo::new();
+        let base = repo.commit(&[], &[("README", "base\n")], "base");
+        let reviewed = repo.commit(&[base], &[("a.txt", "a\n")], "Add a");
+        let malicious = repo.commit(&[base], &[("a.txt", "evil\n")], "Add a");
+
+        // Mallory, who nobody trusts, claims Alice's reviewed commit has the
+        // patch ID of the malicious one.
+        let (alice, mallory) = (testing::id(), testing::id());
+        let mut forged = review(&repo, &mallory, reviewed, Rating::Positive);
+        let malicious_patch_id = shared::patch_id(&repo.repository, &repo.find_commit(malicious))
+            .unwrap()
+            .unwrap();
+        forged.review.commit.patch_id = Some(malicious_patch_id.to_string());
+        forged.trust_level = TrustLevel::None;
+        forged.distance = None;
+
+        let evaluation = evaluate(
+            &repo,
+            malicious,
+            vec![review(&repo, &alice, reviewed, Rating::Positive), forged],
+        );
+        assert_eq!(evaluation.status, Status::Unreviewed);
+    }
+
+    /// A linear history with a snapshot taken at `snapshot`, and commits
+    /// related to it in different ways:
+    ///
+    /// ```text
+    /// base - ancestor - snapshot - descendant
+    ///     \
+    ///      same_tree
+    /// ```
+    struct Snapshot {
+        repo: TestRepo,
+        ancestor: git2::Oid,
+        snapshot: git2::Oid,
+        descendant: git2::Oid,
+        same_tree: git2::Oid,
+    }
+
+    impl Snapshot {
+        fn new() -> Self {
+            let repo = TestRepo::new();
+            let base = repo.commit(&[], &[("README", "base\n")], "base");
+            let ancestor = repo.commit(&[base], &[("a.txt", "a\n")], "Add a");
+            let snapshot = repo.commit(&[ancestor], &[("b.txt", "b\n")], "Add b");
+            let descendant = repo.commit(&[snapshot], &[("c.txt", "c\n")], "Add c");
+            let same_tree = repo.commit(
+                &[base],
+                &[("a.txt", "a\n"), ("b.txt", "b\n")],
+                "Add a and b",
+            );
+            Self {
+                repo,
+                ancestor,
+                snapshot,
+                descendant,
+                same_tree,
+            }
+        }
+
+        /// A review of `kind` by a new Id of the snapshot commit.
+        fn declaration(&self, kind: &str, trust_level: TrustLevel) -> TrustedReview {
+            let mut review = review(&self.repo, &testing::id(), self.snapshot, Rating::Positive);
+            review.review.common.kind = Some(kind.into());
+            review.trust_level = trust_level;
+            review
+        }
+
+        fn evaluate(&self, commit_id: git2::Oid, declaration: TrustedReview) -> Evaluation {
+            evaluate(&self.repo, commit_id, vec![declaration])
+        }
+    }
+
+    #[test]
+    fn snapshot_covers_its_history_and_tree() {
+        let s = Snapshot::new();
+        for commit_id in &[s.ancestor, s.snapshot, s.same_tree] {
+            let evaluation = s.evaluate(
+                *commit_id,
+                s.declaration(proof::SNAPSHOT_KIND, TrustLevel::Medium),
+            );
+            assert_eq!(
+                evaluation,
+                Evaluation {
+                    status: Status::Verified,
+                    equivalence: Some(Equivalence::Snapshot),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn snapshot_does_not_cover_descendants() {
+        let s = Snapshot::new();
+        let evaluation = s.evaluate(
+            s.descendant,
+            s.declaration(proof::SNAPSHOT_KIND, TrustLevel::Medium),
+        );
+        assert_eq!(evaluation.status, Status::Unreviewed);
+    }
+
+    #[test]
+    fn snapshot_must_satisfy_policy() {
+        let s = Snapshot::new();
+        let untrusted = s.evaluate(
+            s.ancestor,
+            s.declaration(proof::SNAPSHOT_KIND, TrustLevel::None),
+        );
+        assert_eq!(untrusted.status, Status::Unreviewed);
+
+        let policy = policy::Policy {
+            min_trust_level: TrustLevel::High,
+            ..policy::Policy::default()
+        };
+        let below_policy = evaluate_under(
+            policy,
+            &s.repo,
+            s.ancestor,
+            vec![s.declaration(proof::SNAPSHOT_KIND, TrustLevel::Medium)],
+        );
+        assert_eq!(below_policy.status, Status::Unreviewed);
+    }
+
+    #[test]
+    fn trusted_baseline_covers_its_history() {
+        let s = Snapshot::new();
+        let covered = s.evaluate(
+            s.ancestor,
+            s.declaration(proof::BASELINE_KIND, TrustLevel::Medium),
+        );
+        assert_eq!(
+            covered,
+            Evaluation {
+                status: Status::Verified,
+                equivalence: Some(Equivalence::Baseline),
+            }
+        );
+
+        let descendant = s.evaluate(
+            s.descendant,
+            s.declaration(proof::BASELINE_KIND, TrustLevel::Medium),
+        );
+        assert_eq!(descendant.status, Status::Unreviewed);
+
+        let untrusted = s.evaluate(
+            s.ancestor,
+            s.declaration(proof::BASELINE_KIND, TrustLevel::None),
+        );
+        assert_eq!(untrusted.status, Status::Unreviewed);
+    }
+}